@@ -0,0 +1,101 @@
+use std::{future::Future, sync::mpsc};
+use crate::{
+    compositor::{CompositorClient, DropZone},
+    errors::ModuleError,
+    settings::ActionSequence,
+};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone)]
+pub enum ActionRequest {
+    Perform(ActionSequence),
+    MoveToWorkspace { workspace_id: u64 },
+    MoveToOutput { output: String },
+    Drop { target_id: u64, zone: DropZone },
+}
+
+#[derive(Debug)]
+enum ActionTarget {
+    Window { window_id: u64, request: ActionRequest },
+    FocusWorkspace { workspace_id: u64 },
+}
+
+#[derive(Debug)]
+struct ActionJob {
+    target: ActionTarget,
+    reply: async_channel::Sender<Result<(), ModuleError>>,
+}
+
+#[derive(Debug)]
+pub struct ActionQueue {
+    jobs: mpsc::Sender<ActionJob>,
+}
+
+impl ActionQueue {
+    pub fn start(compositor: CompositorClient) -> Self {
+        let (jobs, pending) = mpsc::channel::<ActionJob>();
+
+        std::thread::spawn(move || {
+            for job in pending {
+                let result = match job.target {
+                    ActionTarget::Window { window_id, request } => run_request(&compositor, window_id, &request),
+                    ActionTarget::FocusWorkspace { workspace_id } => compositor.focus_workspace(workspace_id),
+                };
+                let _ = job.reply.send_blocking(result);
+            }
+        });
+
+        Self { jobs }
+    }
+
+    pub fn submit(&self, window_id: u64, request: ActionRequest) -> impl Future<Output = Result<(), ModuleError>> {
+        self.queue(ActionTarget::Window { window_id, request })
+    }
+
+    pub fn focus_workspace(&self, workspace_id: u64) -> impl Future<Output = Result<(), ModuleError>> {
+        self.queue(ActionTarget::FocusWorkspace { workspace_id })
+    }
+
+    fn queue(&self, target: ActionTarget) -> impl Future<Output = Result<(), ModuleError>> {
+        let (reply, result) = async_channel::bounded(1);
+        let queued = self.jobs.send(ActionJob { target, reply }).is_ok();
+
+        async move {
+            if !queued {
+                return Err(ModuleError::ActionQueueClosed);
+            }
+            result.recv().await.unwrap_or(Err(ModuleError::ActionQueueClosed))
+        }
+    }
+}
+
+fn run_request(compositor: &CompositorClient, window_id: u64, request: &ActionRequest) -> Result<(), ModuleError> {
+    match request {
+        ActionRequest::Perform(sequence) => run_sequence(compositor, window_id, sequence),
+        ActionRequest::MoveToWorkspace { workspace_id } => compositor.move_window_to_workspace(window_id, *workspace_id),
+        ActionRequest::MoveToOutput { output } => compositor.move_window_to_output(window_id, output),
+        ActionRequest::Drop { target_id, zone } => compositor.drop_window(window_id, *target_id, *zone),
+    }
+}
+
+fn run_sequence(compositor: &CompositorClient, window_id: u64, sequence: &ActionSequence) -> Result<(), ModuleError> {
+    let mut first_error = None;
+
+    for (index, action) in sequence.actions().iter().enumerate() {
+        if index > 0 && !sequence.delay().is_zero() {
+            std::thread::sleep(sequence.delay());
+        }
+
+        if let Err(e) = compositor.perform_action(window_id, action) {
+            if sequence.stop_on_error() {
+                return Err(e);
+            }
+            tracing::warn!(%e, id = window_id, ?action, "sequence step failed; continuing");
+            first_error.get_or_insert(e);
+        }
+    }
+
+    first_error.map_or(Ok(()), Err)
+}
@@ -0,0 +1,148 @@
+use futures::executor::block_on;
+use serde_json::json;
+use super::*;
+use crate::{
+    settings::{Settings, WindowAction},
+    testing::MockNiri,
+};
+
+fn queue_for(mock: &MockNiri) -> ActionQueue {
+    ActionQueue::start(CompositorClient::with_socket_path(
+        Settings::default(),
+        Some(mock.socket_path().to_path_buf()),
+    ))
+}
+
+#[test]
+fn actions_run_in_submission_order() {
+    let mock = MockNiri::start();
+    let queue = queue_for(&mock);
+
+    let pending: Vec<_> = [
+        (1, WindowAction::FocusWindow),
+        (2, WindowAction::CloseWindow),
+        (1, WindowAction::ToggleWindowFloating),
+    ]
+    .into_iter()
+    .map(|(id, action)| queue.submit(id, ActionRequest::Perform(action.into())))
+    .collect();
+
+    for result in pending.into_iter().rev() {
+        block_on(result).unwrap();
+    }
+
+    assert_eq!(
+        mock.actions(),
+        [
+            json!({"FocusWindow": {"id": 1}}),
+            json!({"CloseWindow": {"id": 2}}),
+            json!({"ToggleWindowFloating": {"id": 1}}),
+        ]
+    );
+}
+
+#[test]
+fn failures_are_reported_to_the_submitter() {
+    let mock = MockNiri::start().failing_action("CloseWindow");
+    let queue = queue_for(&mock);
+
+    let failed = queue.submit(3, ActionRequest::Perform(WindowAction::CloseWindow.into()));
+    let next = queue.submit(3, ActionRequest::Perform(WindowAction::FocusWindow.into()));
+
+    assert!(matches!(block_on(failed), Err(ModuleError::CompositorReply(_))));
+    assert!(block_on(next).is_ok());
+}
+
+#[test]
+fn drops_run_on_the_queue() {
+    let windows = vec![crate::testing::window(4, 1, Some((2, 1))), crate::testing::window(5, 1, Some((1, 1)))];
+    let mock = MockNiri::start().with_windows(windows);
+    let queue = queue_for(&mock);
+
+    block_on(queue.submit(4, ActionRequest::Drop { target_id: 5, zone: DropZone::Before })).unwrap();
+
+    assert_eq!(mock.actions(), [json!({"FocusWindow": {"id": 4}}), json!({"MoveColumnToIndex": {"index": 1}})]);
+}
+
+#[test]
+fn submenu_moves_target_the_clicked_window() {
+    let mock = MockNiri::start();
+    let queue = queue_for(&mock);
+
+    block_on(queue.submit(5, ActionRequest::MoveToWorkspace { workspace_id: 3 })).unwrap();
+    block_on(queue.submit(5, ActionRequest::MoveToOutput { output: "HDMI-A-1".to_string() })).unwrap();
+
+    assert_eq!(
+        mock.actions(),
+        [
+            json!({"MoveWindowToWorkspace": {"window_id": 5, "reference": {"Id": 3}, "focus": false}}),
+            json!({"MoveWindowToMonitor": {"id": 5, "output": "HDMI-A-1"}}),
+        ]
+    );
+}
+
+#[test]
+fn workspace_focus_needs_no_window() {
+    let mock = MockNiri::start();
+    let queue = queue_for(&mock);
+
+    block_on(queue.focus_workspace(4)).unwrap();
+
+    assert_eq!(mock.actions(), [json!({"FocusWorkspace": {"reference": {"Id": 4}}})]);
+}
+
+fn sequence(config: serde_json::Value) -> ActionSequence {
+    serde_json::from_value(config).expect("action sequence")
+}
+
+#[test]
+fn sequence_runs_every_step_in_order() {
+    let mock = MockNiri::start();
+    let queue = queue_for(&mock);
+
+    let actions = sequence(json!(["toggle-window-floating", "center-window"]));
+    block_on(queue.submit(5, ActionRequest::Perform(actions))).unwrap();
+
+    assert_eq!(
+        mock.actions(),
+        [json!({"ToggleWindowFloating": {"id": 5}}), json!({"CenterWindow": {"id": 5}})]
+    );
+}
+
+#[test]
+fn sequence_stops_on_error_by_default() {
+    let mock = MockNiri::start().failing_action("CloseWindow");
+    let queue = queue_for(&mock);
+
+    let actions = sequence(json!(["close-window", "focus-window"]));
+    assert!(block_on(queue.submit(5, ActionRequest::Perform(actions))).is_err());
+
+    assert_eq!(mock.actions(), [json!({"CloseWindow": {"id": 5}})]);
+}
+
+#[test]
+fn sequence_can_continue_after_error() {
+    let mock = MockNiri::start().failing_action("CloseWindow");
+    let queue = queue_for(&mock);
+
+    let actions = sequence(json!({
+        "actions": ["close-window", "focus-window"],
+        "stop_on_error": false,
+        "delay_ms": 20,
+    }));
+    assert_eq!(actions.delay(), std::time::Duration::from_millis(20));
+    assert!(block_on(queue.submit(5, ActionRequest::Perform(actions))).is_err());
+
+    assert_eq!(mock.actions(), [json!({"CloseWindow": {"id": 5}}), json!({"FocusWindow": {"id": 5}})]);
+}
+
+#[test]
+fn sequence_config_forms() {
+    assert_eq!(sequence(json!("menu")), WindowAction::Menu.into());
+    assert!(sequence(json!(["focus-window", "menu"])).opens_menu());
+    assert!(sequence(json!({"actions": ["none", "menu"]})).is_noop());
+    assert_eq!(sequence(json!({"niri": "MoveColumnToFirst"})).actions().len(), 1);
+
+    assert!(serde_json::from_value::<ActionSequence>(json!({"actions": [], "delay_ms": 5000})).is_err());
+    assert!(serde_json::from_value::<ActionSequence>(json!(["focus-window", "no-such-action"])).is_err());
+}
@@ -0,0 +1,187 @@
+use std::{
+    os::unix::process::{CommandExt, ExitStatusExt},
+    process::{Command, ExitStatus, Stdio},
+};
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+use waybar_cffi::gtk::glib;
+use crate::errors::ModuleError;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandTemplate {
+    argv: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct CommandContext<'a> {
+    pub id: u64,
+    pub app_id: Option<&'a str>,
+    pub title: Option<&'a str>,
+    pub pid: Option<i32>,
+    pub workspace_id: Option<u64>,
+    pub output: Option<&'a str>,
+}
+
+impl CommandContext<'_> {
+    fn lookup(&self, name: &str) -> Option<String> {
+        let value = match name {
+            "id" => self.id.to_string(),
+            "app_id" => self.app_id.unwrap_or_default().to_string(),
+            "title" => self.title.unwrap_or_default().to_string(),
+            "pid" => self.pid.map(|pid| pid.to_string()).unwrap_or_default(),
+            "workspace_id" => self.workspace_id.map(|id| id.to_string()).unwrap_or_default(),
+            "output" => self.output.unwrap_or_default().to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum CommandParseError {
+    #[error("command is empty")]
+    Empty,
+
+    #[error("unterminated {0} quote")]
+    UnterminatedQuote(char),
+}
+
+impl CommandTemplate {
+    pub fn parse(command: &str) -> Result<Self, CommandParseError> {
+        let argv = split_arguments(command)?;
+        if argv.is_empty() {
+            return Err(CommandParseError::Empty);
+        }
+
+        Ok(Self { argv })
+    }
+
+    pub fn render(&self, context: &CommandContext) -> Vec<String> {
+        self.argv
+            .iter()
+            .map(|argument| substitute(argument, |name| context.lookup(name)))
+            .collect()
+    }
+
+    #[tracing::instrument(level = "DEBUG", skip(self), err)]
+    pub fn spawn(&self, context: &CommandContext) -> Result<(), ModuleError> {
+        let argv = self.render(context);
+        let program = argv[0].clone();
+
+        let child = Command::new(&program)
+            .args(&argv[1..])
+            .stdin(Stdio::null())
+            .process_group(0)
+            .spawn()
+            .map_err(|source| ModuleError::CommandSpawn { program: program.clone(), source })?;
+
+        glib::child_watch_add(glib::Pid(child.id() as i32), move |_, status| {
+            let status = ExitStatus::from_raw(status);
+            if status.success() {
+                tracing::debug!(program, %status, "command finished");
+            } else {
+                tracing::warn!(program, %status, "command exited unsuccessfully");
+            }
+        });
+
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for CommandTemplate {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let command = String::deserialize(deserializer)?;
+        Self::parse(&command).map_err(serde::de::Error::custom)
+    }
+}
+
+fn split_arguments(command: &str) -> Result<Vec<String>, CommandParseError> {
+    let mut arguments = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                arguments.extend(current.take());
+            }
+            '\'' => {
+                let argument = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => argument.push(c),
+                        None => return Err(CommandParseError::UnterminatedQuote('\'')),
+                    }
+                }
+            }
+            '"' => {
+                let argument = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => argument.push(c),
+                            Some(c) => {
+                                argument.push('\\');
+                                argument.push(c);
+                            }
+                            None => return Err(CommandParseError::UnterminatedQuote('"')),
+                        },
+                        Some(c) => argument.push(c),
+                        None => return Err(CommandParseError::UnterminatedQuote('"')),
+                    }
+                }
+            }
+            '\\' => {
+                let argument = current.get_or_insert_with(String::new);
+                if let Some(c) = chars.next() {
+                    argument.push(c);
+                }
+            }
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    arguments.extend(current);
+    Ok(arguments)
+}
+
+/// Fills in `{name}` placeholders. `{{` and `}}` stand for literal braces, and braces that
+/// don't form a known placeholder are kept as they are.
+fn substitute(argument: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut result = String::with_capacity(argument.len());
+    let mut rest = argument;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(escaped) = rest.strip_prefix("{{").or_else(|| rest.strip_prefix("}}")) {
+            result.push_str(&rest[..1]);
+            rest = escaped;
+            continue;
+        }
+
+        let placeholder = rest[1..].find(['{', '}']).filter(|&end| rest[1 + end..].starts_with('}'));
+        match placeholder.and_then(|end| Some((end, value(&rest[1..1 + end])?))) {
+            Some((end, filled)) => {
+                result.push_str(&filled);
+                rest = &rest[end + 2..];
+            }
+            None => {
+                result.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
@@ -0,0 +1,76 @@
+use super::*;
+
+fn context() -> CommandContext<'static> {
+    CommandContext {
+        id: 42,
+        app_id: Some("org.example.App"),
+        title: Some("it's \"quoted\"; rm -rf ~ $(reboot)"),
+        pid: Some(1234),
+        workspace_id: Some(3),
+        output: Some("DP-1"),
+    }
+}
+
+#[test]
+fn arguments_split_like_a_shell() {
+    let command = CommandTemplate::parse(r#"notify-send 'two words' "say \"hi\"" back\ slash"#).unwrap();
+
+    assert_eq!(command.argv, ["notify-send", "two words", "say \"hi\"", "back slash"]);
+}
+
+#[test]
+fn placeholders_never_split_or_escape_arguments() {
+    let command = CommandTemplate::parse("tool --window {id} --title={title} {app_id} {pid} {workspace_id} {output}").unwrap();
+
+    assert_eq!(
+        command.render(&context()),
+        [
+            "tool",
+            "--window",
+            "42",
+            "--title=it's \"quoted\"; rm -rf ~ $(reboot)",
+            "org.example.App",
+            "1234",
+            "3",
+            "DP-1",
+        ]
+    );
+}
+
+#[test]
+fn missing_values_render_empty() {
+    let command = CommandTemplate::parse("tool {id} {title} {output}").unwrap();
+    let context = CommandContext { id: 7, ..Default::default() };
+
+    assert_eq!(command.render(&context), ["tool", "7", "", ""]);
+}
+
+#[test]
+fn doubled_brace_is_literal() {
+    let command = CommandTemplate::parse("jq '{{\"id\": {id}}'").unwrap();
+
+    assert_eq!(command.render(&context()), ["jq", "{\"id\": 42}"]);
+}
+
+#[test]
+fn invalid_commands_are_rejected() {
+    assert_eq!(CommandTemplate::parse("  "), Err(CommandParseError::Empty));
+    assert_eq!(CommandTemplate::parse("echo 'open"), Err(CommandParseError::UnterminatedQuote('\'')));
+}
+
+#[test]
+fn other_braces_pass_through() {
+    let command = CommandTemplate::parse("find . -name {title} -exec echo {} {window} {id + }}").unwrap();
+
+    assert_eq!(
+        command.render(&context()),
+        ["find", ".", "-name", "it's \"quoted\"; rm -rf ~ $(reboot)", "-exec", "echo", "{}", "{window}", "{id", "+", "}"]
+    );
+}
+
+#[test]
+fn spawn_reports_missing_program() {
+    let command = CommandTemplate::parse("/nonexistent/niri-window-buttons-test {id}").unwrap();
+
+    assert!(matches!(command.spawn(&context()), Err(ModuleError::CommandSpawn { .. })));
+}
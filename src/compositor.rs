@@ -1,169 +1,298 @@
-use std::{collections::HashMap, ops::Deref};
+use std::{
+    collections::{BTreeSet, HashMap},
+    convert::Infallible,
+    fmt::Debug,
+    io::ErrorKind,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    thread::JoinHandle,
+    time::Duration,
+};
 use async_channel::{Receiver, Sender};
-use futures::Stream;
-use niri_ipc::{Action, Event, Output, Reply, Request, Workspace, socket::Socket};
-use crate::{errors::ModuleError, settings::Settings};
+use itertools::Itertools;
+use niri_ipc::{Action, Event, Output, Reply, Request, WindowLayout, Workspace, WorkspaceReferenceArg, socket::Socket};
+use crate::{
+    command::{CommandContext, CommandTemplate},
+    errors::ModuleError,
+    settings::{NiriAction, Settings, WindowAction, WindowProperty},
+};
+
+mod recording;
+#[cfg(test)]
+mod tests;
+
+use recording::{EventRecorder, ReplayEvent};
 
 #[derive(Debug, Clone)]
 pub struct CompositorClient {
     settings: Settings,
+    socket_path: Option<PathBuf>,
+    connection: Arc<Mutex<RequestConnection>>,
+    events: Arc<EventHub>,
+    open_order: Arc<Mutex<OpenOrder>>,
 }
 
 impl CompositorClient {
     pub fn create(settings: Settings) -> Self {
-        Self { settings }
+        Self::with_socket_path(settings, None)
+    }
+
+    pub fn with_socket_path(settings: Settings, socket_path: Option<PathBuf>) -> Self {
+        Self {
+            settings,
+            connection: Arc::new(Mutex::new(RequestConnection::new(socket_path.clone()))),
+            socket_path,
+            events: Arc::new(EventHub::default()),
+            open_order: Arc::default(),
+        }
+    }
+
+    pub fn perform_action(&self, window_id: u64, action: &WindowAction) -> Result<(), ModuleError> {
+        match action {
+            WindowAction::None | WindowAction::Menu => Ok(()),
+            WindowAction::FocusWindow => self.focus_window(window_id),
+            WindowAction::CloseWindow => self.close_window(window_id),
+            WindowAction::MaximizeColumn => self.maximize_window_column(window_id),
+            WindowAction::MaximizeWindowToEdges => self.maximize_window_to_edges(window_id),
+            WindowAction::CenterColumn => self.center_column(window_id),
+            WindowAction::CenterWindow => self.center_window(window_id),
+            WindowAction::CenterVisibleColumns => self.center_visible_columns(window_id),
+            WindowAction::ExpandColumnToAvailableWidth => self.expand_column_to_available_width(window_id),
+            WindowAction::FullscreenWindow => self.fullscreen_window(window_id),
+            WindowAction::ToggleWindowedFullscreen => self.toggle_windowed_fullscreen(window_id),
+            WindowAction::ToggleWindowFloating => self.toggle_floating(window_id),
+            WindowAction::ConsumeWindowIntoColumn => self.consume_window_into_column(window_id),
+            WindowAction::ExpelWindowFromColumn => self.expel_window_from_column(window_id),
+            WindowAction::ResetWindowHeight => self.reset_window_height(window_id),
+            WindowAction::SwitchPresetColumnWidth => self.switch_preset_column_width(window_id),
+            WindowAction::SwitchPresetWindowHeight => self.switch_preset_window_height(window_id),
+            WindowAction::MoveWindowToWorkspaceDown => self.move_window_to_workspace_down(window_id),
+            WindowAction::MoveWindowToWorkspaceUp => self.move_window_to_workspace_up(window_id),
+            WindowAction::MoveWindowToMonitorLeft => self.move_window_to_monitor_left(window_id),
+            WindowAction::MoveWindowToMonitorRight => self.move_window_to_monitor_right(window_id),
+            WindowAction::ToggleColumnTabbedDisplay => self.toggle_column_tabbed_display(window_id),
+            WindowAction::FocusWorkspacePrevious => self.focus_workspace_previous(window_id),
+            WindowAction::Niri(action) => self.perform_niri_action(window_id, action),
+            WindowAction::Exec(command) => self.run_command(window_id, command),
+        }
+    }
+
+    #[tracing::instrument(level = "TRACE", err)]
+    pub fn run_command(&self, window_id: u64, command: &CommandTemplate) -> Result<(), ModuleError> {
+        let windows = self.query_windows()?;
+        let window = windows.iter().find(|w| w.id == window_id);
+        if window.is_none() {
+            tracing::warn!(id = window_id, "window not found; running command with partial placeholders");
+        }
+
+        let workspace_id = window.and_then(|w| w.workspace_id);
+        let output = match workspace_id {
+            Some(workspace_id) => self
+                .query_workspaces()?
+                .into_iter()
+                .find(|ws| ws.id == workspace_id)
+                .and_then(|ws| ws.output),
+            None => None,
+        };
+
+        command.spawn(&CommandContext {
+            id: window_id,
+            app_id: window.and_then(|w| w.app_id.as_deref()),
+            title: window.and_then(|w| w.title.as_deref()),
+            pid: window.and_then(|w| w.pid),
+            workspace_id,
+            output: output.as_deref(),
+        })
+    }
+
+    #[tracing::instrument(level = "TRACE", err)]
+    pub fn perform_niri_action(&self, window_id: u64, action: &NiriAction) -> Result<(), ModuleError> {
+        let (action, targets_window) = action.for_window(window_id).map_err(ModuleError::InvalidNiriAction)?;
+        if !targets_window {
+            self.focus_window(window_id)?;
+        }
+        let response = self.send_request(Request::Action(action))?;
+        validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn focus_window(&self, window_id: u64) -> Result<(), ModuleError> {
-        let response = send_request(Request::Action(Action::FocusWindow { id: window_id }))?;
+        let response = self.send_request(Request::Action(Action::FocusWindow { id: window_id }))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn close_window(&self, window_id: u64) -> Result<(), ModuleError> {
-        let response = send_request(Request::Action(Action::CloseWindow { id: Some(window_id) }))?;
+        let response = self.send_request(Request::Action(Action::CloseWindow { id: Some(window_id) }))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn maximize_window_column(&self, window_id: u64) -> Result<(), ModuleError> {
         self.focus_window(window_id)?;
-        let response = send_request(Request::Action(Action::MaximizeColumn {}))?;
+        let response = self.send_request(Request::Action(Action::MaximizeColumn {}))?;
         validate_handled(response)
     }
 
 	#[tracing::instrument(level = "TRACE", err)]
 	pub fn maximize_window_to_edges(&self, window_id: u64) -> Result<(), ModuleError> {
 		self.focus_window(window_id)?;
-		let response = send_request(Request::Action(Action::MaximizeWindowToEdges { id: Some(window_id) }))?;
+		let response = self.send_request(Request::Action(Action::MaximizeWindowToEdges { id: Some(window_id) }))?;
 		validate_handled(response)
 	}
 
 	#[tracing::instrument(level = "TRACE", err)]
 	pub fn center_column(&self, window_id: u64) -> Result<(), ModuleError> {
 		self.focus_window(window_id)?;
-		let response = send_request(Request::Action(Action::CenterColumn {}))?;
+		let response = self.send_request(Request::Action(Action::CenterColumn {}))?;
 		validate_handled(response)
 	}
 
 	#[tracing::instrument(level = "TRACE", err)]
 	pub fn fullscreen_window(&self, window_id: u64) -> Result<(), ModuleError> {
-		let response = send_request(Request::Action(Action::FullscreenWindow { id: Some(window_id) }))?;
+		let response = self.send_request(Request::Action(Action::FullscreenWindow { id: Some(window_id) }))?;
 		validate_handled(response)
 	}
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn toggle_floating(&self, window_id: u64) -> Result<(), ModuleError> {
-        let response = send_request(Request::Action(Action::ToggleWindowFloating { id: Some(window_id) }))?;
+        let response = self.send_request(Request::Action(Action::ToggleWindowFloating { id: Some(window_id) }))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn center_window(&self, window_id: u64) -> Result<(), ModuleError> {
-        let response = send_request(Request::Action(Action::CenterWindow { id: Some(window_id) }))?;
+        let response = self.send_request(Request::Action(Action::CenterWindow { id: Some(window_id) }))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn center_visible_columns(&self, window_id: u64) -> Result<(), ModuleError> {
         self.focus_window(window_id)?;
-        let response = send_request(Request::Action(Action::CenterVisibleColumns {}))?;
+        let response = self.send_request(Request::Action(Action::CenterVisibleColumns {}))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn expand_column_to_available_width(&self, window_id: u64) -> Result<(), ModuleError> {
         self.focus_window(window_id)?;
-        let response = send_request(Request::Action(Action::ExpandColumnToAvailableWidth {}))?;
+        let response = self.send_request(Request::Action(Action::ExpandColumnToAvailableWidth {}))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn toggle_windowed_fullscreen(&self, window_id: u64) -> Result<(), ModuleError> {
-        let response = send_request(Request::Action(Action::ToggleWindowedFullscreen { id: Some(window_id) }))?;
+        let response = self.send_request(Request::Action(Action::ToggleWindowedFullscreen { id: Some(window_id) }))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn consume_window_into_column(&self, window_id: u64) -> Result<(), ModuleError> {
         self.focus_window(window_id)?;
-        let response = send_request(Request::Action(Action::ConsumeWindowIntoColumn {}))?;
+        let response = self.send_request(Request::Action(Action::ConsumeWindowIntoColumn {}))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn expel_window_from_column(&self, window_id: u64) -> Result<(), ModuleError> {
         self.focus_window(window_id)?;
-        let response = send_request(Request::Action(Action::ExpelWindowFromColumn {}))?;
+        let response = self.send_request(Request::Action(Action::ExpelWindowFromColumn {}))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn reset_window_height(&self, window_id: u64) -> Result<(), ModuleError> {
         self.focus_window(window_id)?;
-        let response = send_request(Request::Action(Action::ResetWindowHeight { id: None }))?;
+        let response = self.send_request(Request::Action(Action::ResetWindowHeight { id: None }))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn switch_preset_column_width(&self, window_id: u64) -> Result<(), ModuleError> {
         self.focus_window(window_id)?;
-        let response = send_request(Request::Action(Action::SwitchPresetColumnWidth {}))?;
+        let response = self.send_request(Request::Action(Action::SwitchPresetColumnWidth {}))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn switch_preset_window_height(&self, window_id: u64) -> Result<(), ModuleError> {
         self.focus_window(window_id)?;
-        let response = send_request(Request::Action(Action::SwitchPresetWindowHeight { id: None }))?;
+        let response = self.send_request(Request::Action(Action::SwitchPresetWindowHeight { id: None }))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn move_window_to_workspace_down(&self, window_id: u64) -> Result<(), ModuleError> {
         self.focus_window(window_id)?;
-        let response = send_request(Request::Action(Action::MoveWindowToWorkspaceDown { focus: false }))?;
+        let response = self.send_request(Request::Action(Action::MoveWindowToWorkspaceDown { focus: false }))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn move_window_to_workspace_up(&self, window_id: u64) -> Result<(), ModuleError> {
         self.focus_window(window_id)?;
-        let response = send_request(Request::Action(Action::MoveWindowToWorkspaceUp { focus: false }))?;
+        let response = self.send_request(Request::Action(Action::MoveWindowToWorkspaceUp { focus: false }))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn move_window_to_monitor_left(&self, window_id: u64) -> Result<(), ModuleError> {
         self.focus_window(window_id)?;
-        let response = send_request(Request::Action(Action::MoveWindowToMonitorLeft {}))?;
+        let response = self.send_request(Request::Action(Action::MoveWindowToMonitorLeft {}))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn move_window_to_monitor_right(&self, window_id: u64) -> Result<(), ModuleError> {
         self.focus_window(window_id)?;
-        let response = send_request(Request::Action(Action::MoveWindowToMonitorRight {}))?;
+        let response = self.send_request(Request::Action(Action::MoveWindowToMonitorRight {}))?;
+        validate_handled(response)
+    }
+
+    #[tracing::instrument(level = "TRACE", err)]
+    pub fn move_window_to_workspace(&self, window_id: u64, workspace_id: u64) -> Result<(), ModuleError> {
+        let response = self.send_request(Request::Action(Action::MoveWindowToWorkspace {
+            window_id: Some(window_id),
+            reference: WorkspaceReferenceArg::Id(workspace_id),
+            focus: false,
+        }))?;
+        validate_handled(response)
+    }
+
+    #[tracing::instrument(level = "TRACE", err)]
+    pub fn move_window_to_output(&self, window_id: u64, output: &str) -> Result<(), ModuleError> {
+        let response = self.send_request(Request::Action(Action::MoveWindowToMonitor {
+            id: Some(window_id),
+            output: output.to_string(),
+        }))?;
+        validate_handled(response)
+    }
+
+    #[tracing::instrument(level = "TRACE", err)]
+    pub fn focus_workspace(&self, workspace_id: u64) -> Result<(), ModuleError> {
+        let response = self.send_request(Request::Action(Action::FocusWorkspace {
+            reference: WorkspaceReferenceArg::Id(workspace_id),
+        }))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn toggle_column_tabbed_display(&self, window_id: u64) -> Result<(), ModuleError> {
         self.focus_window(window_id)?;
-        let response = send_request(Request::Action(Action::ToggleColumnTabbedDisplay {}))?;
+        let response = self.send_request(Request::Action(Action::ToggleColumnTabbedDisplay {}))?;
         validate_handled(response)
     }
 
     #[tracing::instrument(level = "TRACE", err)]
     pub fn focus_workspace_previous(&self, window_id: u64) -> Result<(), ModuleError> {
         self.focus_window(window_id)?;
-        let response = send_request(Request::Action(Action::FocusWorkspacePrevious {}))?;
+        let response = self.send_request(Request::Action(Action::FocusWorkspacePrevious {}))?;
         validate_handled(response)
     }
 
     pub fn query_outputs(&self) -> Result<HashMap<String, Output>, ModuleError> {
-        let response = send_request(Request::Outputs)?;
+        let response = self.send_request(Request::Outputs)?;
         match response {
             Ok(niri_ipc::Response::Outputs(outputs)) => Ok(outputs),
             Ok(other) => Err(ModuleError::unexpected_response("Outputs", other)),
@@ -171,93 +300,208 @@ impl CompositorClient {
         }
     }
 
-    pub fn create_window_stream(&self) -> WindowEventStream {
-        WindowEventStream::start(self.settings.only_current_workspace())
+    pub fn query_windows(&self) -> Result<Vec<niri_ipc::Window>, ModuleError> {
+        let response = self.send_request(Request::Windows)?;
+        match response {
+            Ok(niri_ipc::Response::Windows(windows)) => Ok(windows),
+            Ok(other) => Err(ModuleError::unexpected_response("Windows", other)),
+            Err(msg) => Err(ModuleError::CompositorReply(msg)),
+        }
     }
 
-    pub fn create_workspace_stream(&self) -> Result<impl Stream<Item = Vec<Workspace>>, ModuleError> {
-        let mut socket = connect_socket()?;
-        let response = socket.send(Request::EventStream).map_err(ModuleError::CompositorIpc)?;
-        validate_handled(response)?;
+    pub fn query_workspaces(&self) -> Result<Vec<Workspace>, ModuleError> {
+        let response = self.send_request(Request::Workspaces)?;
+        match response {
+            Ok(niri_ipc::Response::Workspaces(workspaces)) => Ok(workspaces),
+            Ok(other) => Err(ModuleError::unexpected_response("Workspaces", other)),
+            Err(msg) => Err(ModuleError::CompositorReply(msg)),
+        }
+    }
 
-        let mut event_reader = socket.read_events();
-        Ok(async_stream::stream! {
-            loop {
-                match event_reader() {
-                    Ok(Event::WorkspacesChanged { workspaces }) => yield workspaces,
-                    Ok(_) => {},
-                    Err(e) => {
-                        tracing::error!(%e, "workspace event stream error");
-                        break;
-                    }
-                }
-            }
-        })
+    /// Workspaces as last seen on the event stream, without a round trip to the compositor.
+    pub fn current_workspaces(&self) -> Vec<Workspace> {
+        self.events
+            .latest(|event| match event {
+                CompositorEvent::Workspaces(workspaces) => Some(workspaces.clone()),
+                _ => None,
+            })
+            .unwrap_or_default()
     }
 
+    pub fn subscribe_events(&self) -> CompositorEventStream {
+        let receiver = self.events.subscribe();
+
+        self.events.reader.get_or_init(|| {
+            let client = self.clone();
+            let filter_workspace = self.settings.only_current_workspace();
+            std::thread::spawn(move || match client.settings.debug_replay_events() {
+                Some(path) => run_event_replay(&client, path, filter_workspace),
+                None => run_event_reader(client, filter_workspace),
+            })
+        });
+
+        CompositorEventStream { receiver }
+    }
+
+    /// Puts a window before, after or into the column of `target_id`, moving it to the target's
+    /// workspace by id first when it's on another one.
     #[tracing::instrument(level = "TRACE", err)]
-    pub fn reposition_window(&self, window_id: u64, position_delta: i32) -> Result<(), ModuleError> {
-        if position_delta == 0 {
+    pub fn drop_window(&self, window_id: u64, target_id: u64, zone: DropZone) -> Result<(), ModuleError> {
+        if window_id == target_id {
             return Ok(());
         }
 
-        tracing::info!("repositioning window {} by {} columns", window_id, position_delta);
+        let mut all_windows = self.query_windows()?;
+        let currently_focused = all_windows.iter().find(|w| w.is_focused).map(|w| w.id);
+        let (window, target) = find_pair(&all_windows, window_id, target_id)?;
+        let invalid = |reason| ModuleError::InvalidDrop { window_id, reason };
+
+        if target.layout.pos_in_scrolling_layout.is_none() {
+            return Err(invalid("the target window is floating"));
+        }
 
-        let response = send_request(Request::Windows)?;
-        let all_windows: Vec<niri_ipc::Window> = match response {
-            Ok(niri_ipc::Response::Windows(windows)) => windows,
-            Ok(other) => return Err(ModuleError::unexpected_response("Windows", other)),
-            Err(msg) => return Err(ModuleError::CompositorReply(msg)),
-        };
+        if window.workspace_id != target.workspace_id {
+            let workspace_id = target.workspace_id.ok_or(invalid("the target window has no workspace"))?;
+            self.move_window_to_workspace(window_id, workspace_id)?;
 
-        let currently_focused = all_windows.iter().find(|w| w.is_focused).map(|w| w.id);
+            if window.layout.pos_in_scrolling_layout.is_none() {
+                return Ok(());
+            }
+            all_windows = self.query_windows()?;
+        }
 
-        let target_window = all_windows.iter().find(|w| w.id == window_id);
-        let Some(target) = target_window else {
-            tracing::warn!("target window not found in window list");
-            return Ok(());
+        let (window, target) = find_pair(&all_windows, window_id, target_id)?;
+        let (Some((mut column, _)), Some((mut target_column, _))) =
+            (window.layout.pos_in_scrolling_layout, target.layout.pos_in_scrolling_layout)
+        else {
+            return Err(invalid("floating windows have no column"));
         };
+        if zone == DropZone::Into && column == target_column {
+            return Ok(());
+        }
 
-        let tile_position = target.layout.pos_in_scrolling_layout.map(|(_, tile)| tile).unwrap_or(1);
-        let is_stacked = tile_position > 1;
+        let column_size = all_windows
+            .iter()
+            .filter(|w| w.workspace_id == window.workspace_id)
+            .filter(|w| w.layout.pos_in_scrolling_layout.is_some_and(|(c, _)| c == column))
+            .count();
 
         self.focus_window(window_id)?;
 
-        if is_stacked {
+        if column_size > 1 {
             tracing::trace!("expelling stacked window from column");
-            let response = send_request(Request::Action(Action::ExpelWindowFromColumn {}))?;
+            let response = self.send_request(Request::Action(Action::ExpelWindowFromColumn {}))?;
             validate_handled(response)?;
+
+            // The expelled window gets a new column right after its old one.
+            if target_column > column {
+                target_column += 1;
+            }
+            column += 1;
         }
 
-        let (action, count) = if position_delta < 0 {
-            (Action::MoveColumnLeft {}, position_delta.abs())
-        } else {
-            (Action::MoveColumnRight {}, position_delta)
+        let index = match zone {
+            DropZone::Before if column < target_column => target_column - 1,
+            DropZone::Before => target_column,
+            DropZone::After | DropZone::Into if column < target_column => target_column,
+            DropZone::After | DropZone::Into => target_column + 1,
         };
+        if index != column {
+            let response = self.send_request(Request::Action(Action::MoveColumnToIndex { index }))?;
+            validate_handled(response)?;
+        }
 
-        for _ in 0..count {
-            let response = send_request(Request::Action(action.clone()))?;
+        if zone == DropZone::Into {
+            let response = self.send_request(Request::Action(Action::ConsumeOrExpelWindowLeft { id: Some(window_id) }))?;
             validate_handled(response)?;
         }
 
-        if let Some(original_focus) = currently_focused {
-            if original_focus != window_id {
-                self.focus_window(original_focus)?;
-            }
+        if let Some(original_focus) = currently_focused.filter(|id| *id != window_id) {
+            self.focus_window(original_focus)?;
         }
 
         Ok(())
     }
 }
 
-#[tracing::instrument(level = "TRACE", err)]
-fn send_request(request: Request) -> Result<Reply, ModuleError> {
-    connect_socket()?.send(request).map_err(ModuleError::CompositorIpc)
+fn find_pair(windows: &[niri_ipc::Window], window_id: u64, target_id: u64) -> Result<(&niri_ipc::Window, &niri_ipc::Window), ModuleError> {
+    let find = |id| windows.iter().find(|w| w.id == id).ok_or(ModuleError::UnknownWindow(id));
+    Ok((find(window_id)?, find(target_id)?))
+}
+
+impl CompositorClient {
+    #[tracing::instrument(level = "TRACE", skip(self), err)]
+    fn send_request(&self, request: Request) -> Result<Reply, ModuleError> {
+        self.connection.lock().expect("request connection lock").send(request)
+    }
+}
+
+struct RequestConnection {
+    socket_path: Option<PathBuf>,
+    socket: Option<Socket>,
+}
+
+impl Debug for RequestConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestConnection")
+            .field("socket_path", &self.socket_path)
+            .field("connected", &self.socket.is_some())
+            .finish()
+    }
+}
+
+impl RequestConnection {
+    fn new(socket_path: Option<PathBuf>) -> Self {
+        Self { socket_path, socket: None }
+    }
+
+    fn send(&mut self, request: Request) -> Result<Reply, ModuleError> {
+        if let Some(socket) = &mut self.socket {
+            match socket.send(request.clone()) {
+                Ok(reply) => return Ok(reply),
+                Err(e) if is_stale_connection(&e) => {
+                    tracing::debug!(%e, "request socket closed by compositor, reconnecting");
+                    self.socket = None;
+                }
+                Err(e) => {
+                    self.socket = None;
+                    return Err(ModuleError::CompositorIpc(e));
+                }
+            }
+        }
+
+        let socket = self.socket.insert(connect_socket(self.socket_path.as_deref())?);
+        match socket.send(request) {
+            Ok(reply) => Ok(reply),
+            Err(e) => {
+                self.socket = None;
+                Err(ModuleError::CompositorIpc(e))
+            }
+        }
+    }
+}
+
+fn is_stale_connection(error: &std::io::Error) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::BrokenPipe | ErrorKind::ConnectionReset | ErrorKind::NotConnected | ErrorKind::UnexpectedEof
+    )
 }
 
 #[tracing::instrument(level = "TRACE", err)]
-fn connect_socket() -> Result<Socket, ModuleError> {
-    Socket::connect().map_err(ModuleError::CompositorIpc)
+fn connect_socket(socket_path: Option<&Path>) -> Result<Socket, ModuleError> {
+    match socket_path {
+        Some(path) => Socket::connect_to(path),
+        None => Socket::connect(),
+    }
+    .map_err(ModuleError::CompositorIpc)
+}
+
+fn open_event_stream(socket_path: Option<&Path>) -> Result<impl FnMut() -> std::io::Result<Event>, ModuleError> {
+    let mut socket = connect_socket(socket_path)?;
+    let response = socket.send(Request::EventStream).map_err(ModuleError::CompositorIpc)?;
+    validate_handled(response)?;
+    Ok(socket.read_events())
 }
 
 fn validate_handled(response: Reply) -> Result<(), ModuleError> {
@@ -268,53 +512,272 @@ fn validate_handled(response: Reply) -> Result<(), ModuleError> {
     }
 }
 
-pub struct WindowEventStream {
-    receiver: Receiver<WindowSnapshot>,
+#[derive(Debug, Clone)]
+pub enum CompositorEvent {
+    Connection { connected: bool },
+    Windows(WindowSnapshot),
+    Workspaces(Vec<Workspace>),
+    Outputs(BTreeSet<String>),
+    Overview { is_open: bool },
+}
+
+pub struct CompositorEventStream {
+    receiver: Receiver<CompositorEvent>,
 }
 
-impl WindowEventStream {
-    fn start(filter_workspace: bool) -> Self {
+impl CompositorEventStream {
+    pub async fn next_event(&self) -> Option<CompositorEvent> {
+        self.receiver.recv().await.ok()
+    }
+}
+
+#[derive(Debug, Default)]
+struct EventHub {
+    reader: OnceLock<JoinHandle<()>>,
+    channels: Mutex<HubChannels>,
+}
+
+#[derive(Debug, Default)]
+struct HubChannels {
+    subscribers: Vec<Sender<CompositorEvent>>,
+    retained: Vec<CompositorEvent>,
+}
+
+impl EventHub {
+    fn subscribe(&self) -> Receiver<CompositorEvent> {
         let (tx, rx) = async_channel::unbounded();
-        std::thread::spawn(move || {
-            if let Err(e) = run_window_stream(tx, filter_workspace) {
-                tracing::error!(%e, "window event stream terminated");
-            }
-        });
+        let mut channels = self.channels.lock().expect("event hub lock");
 
-        Self { receiver: rx }
+        for event in &channels.retained {
+            let _ = tx.send_blocking(event.clone());
+        }
+        channels.subscribers.push(tx);
+
+        rx
     }
 
-    pub async fn next_snapshot(&self) -> Option<WindowSnapshot> {
-        self.receiver.recv().await.ok()
+    fn latest<T>(&self, select: impl FnMut(&CompositorEvent) -> Option<T>) -> Option<T> {
+        self.channels.lock().expect("event hub lock").retained.iter().find_map(select)
+    }
+
+    fn publish(&self, event: CompositorEvent) -> Result<(), ModuleError> {
+        let mut channels = self.channels.lock().expect("event hub lock");
+
+        channels.subscribers.retain(|tx| tx.send_blocking(event.clone()).is_ok());
+        if channels.subscribers.is_empty() {
+            return Err(ModuleError::EventChannelClosed);
+        }
+
+        let kind = std::mem::discriminant(&event);
+        match channels.retained.iter_mut().find(|retained| std::mem::discriminant(*retained) == kind) {
+            Some(retained) => *retained = event,
+            None => channels.retained.push(event),
+        }
+
+        Ok(())
     }
 }
 
-fn run_window_stream(tx: Sender<WindowSnapshot>, filter_workspace: bool) -> Result<(), ModuleError> {
-    let mut socket = connect_socket()?;
-    let response = socket.send(Request::EventStream).map_err(ModuleError::CompositorIpc)?;
-    validate_handled(response)?;
+#[derive(Debug)]
+struct StreamLink {
+    name: &'static str,
+    retry_delay: Duration,
+    connected: bool,
+}
+
+impl StreamLink {
+    const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(250);
+    const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            retry_delay: Self::INITIAL_RETRY_DELAY,
+            connected: false,
+        }
+    }
+
+    fn established(&mut self) {
+        tracing::info!(stream = self.name, "compositor event stream connected");
+        self.retry_delay = Self::INITIAL_RETRY_DELAY;
+        self.connected = true;
+    }
+
+    fn lost(&mut self, error: &ModuleError) -> bool {
+        tracing::warn!(stream = self.name, %error, "compositor event stream interrupted");
+        std::mem::take(&mut self.connected)
+    }
+
+    fn wait_before_retry(&mut self) {
+        tracing::debug!(stream = self.name, delay = ?self.retry_delay, "reconnecting to compositor");
+        std::thread::sleep(self.retry_delay);
+        self.retry_delay = (self.retry_delay * 2).min(Self::MAX_RETRY_DELAY);
+    }
+}
 
-    let mut event_reader = socket.read_events();
-    let mut window_state = WindowTracker::new();
+fn run_event_reader(client: CompositorClient, filter_workspace: bool) {
+    let mut link = StreamLink::new("events");
+    let mut recorder = client.settings.debug_record_events().and_then(|path| {
+        EventRecorder::open(path)
+            .inspect_err(|e| tracing::warn!(%e, "event recording disabled"))
+            .ok()
+    });
 
     loop {
-        match event_reader() {
-            Ok(event) => {
-                if let Some(snapshot) = window_state.process_event(event, filter_workspace) {
-                    tx.send_blocking(snapshot).map_err(|_| ModuleError::SnapshotChannelClosed)?;
-                }
-            }
-            Err(e) => {
-                tracing::error!(%e, "event stream read error");
-                return Err(ModuleError::CompositorIpc(e));
+        let error = match read_events(&client, filter_workspace, &mut link, recorder.as_mut()) {
+            Err(ModuleError::EventChannelClosed) => return,
+            Err(e) => e,
+        };
+
+        if link.lost(&error) && client.events.publish(CompositorEvent::Connection { connected: false }).is_err() {
+            return;
+        }
+        link.wait_before_retry();
+    }
+}
+
+fn read_events(
+    client: &CompositorClient,
+    filter_workspace: bool,
+    link: &mut StreamLink,
+    recorder: Option<&mut EventRecorder>,
+) -> Result<Infallible, ModuleError> {
+    let mut event_reader = open_event_stream(client.socket_path.as_deref())?;
+    let mut dispatcher = EventDispatcher::new(client, filter_workspace, recorder);
+
+    let windows = client.query_windows()?;
+    let workspaces = client.query_workspaces()?;
+
+    link.established();
+    client.events.publish(CompositorEvent::Connection { connected: true })?;
+    dispatcher.dispatch(Event::WindowsChanged { windows })?;
+    dispatcher.dispatch(Event::WorkspacesChanged { workspaces })?;
+
+    loop {
+        dispatcher.dispatch(event_reader().map_err(ModuleError::CompositorIpc)?)?;
+    }
+}
+
+fn run_event_replay(client: &CompositorClient, path: &Path, filter_workspace: bool) {
+    match replay_events(client, path, filter_workspace) {
+        Ok(()) => tracing::info!(path = %path.display(), "event replay finished"),
+        Err(ModuleError::EventChannelClosed) => {}
+        Err(e) => tracing::error!(%e, "event replay failed"),
+    }
+}
+
+fn replay_events(client: &CompositorClient, path: &Path, filter_workspace: bool) -> Result<(), ModuleError> {
+    const MAX_REPLAY_DELAY: Duration = Duration::from_secs(1);
+
+    let events = recording::load_recording(path)?;
+    tracing::info!(path = %path.display(), count = events.len(), "replaying recorded compositor events");
+
+    let mut dispatcher = EventDispatcher::new(client, filter_workspace, None);
+    client.events.publish(CompositorEvent::Connection { connected: true })?;
+
+    for ReplayEvent { delay, event } in events {
+        std::thread::sleep(delay.min(MAX_REPLAY_DELAY));
+        dispatcher.dispatch(event)?;
+    }
+
+    Ok(())
+}
+
+struct EventDispatcher<'a> {
+    hub: &'a EventHub,
+    recorder: Option<&'a mut EventRecorder>,
+    tracker: WindowTracker,
+    filter_workspace: bool,
+    outputs: BTreeSet<String>,
+}
+
+impl<'a> EventDispatcher<'a> {
+    fn new(client: &'a CompositorClient, filter_workspace: bool, recorder: Option<&'a mut EventRecorder>) -> Self {
+        Self {
+            hub: &client.events,
+            recorder,
+            tracker: WindowTracker::new(client.open_order.clone()),
+            filter_workspace,
+            outputs: BTreeSet::new(),
+        }
+    }
+
+    fn dispatch(&mut self, event: Event) -> Result<(), ModuleError> {
+        if let Some(Err(e)) = self.recorder.as_mut().map(|recorder| recorder.record(&event)) {
+            tracing::warn!(%e, "event recording stopped");
+            self.recorder = None;
+        }
+
+        let workspaces_changed = matches!(
+            event,
+            Event::WorkspacesChanged { .. }
+                | Event::WorkspaceActivated { .. }
+                | Event::WorkspaceActiveWindowChanged { .. }
+                | Event::WorkspaceUrgencyChanged { .. }
+        );
+        let windows_changed = workspaces_changed || matches!(
+            event,
+            Event::WindowsChanged { .. }
+                | Event::WindowOpenedOrChanged { .. }
+                | Event::WindowClosed { .. }
+                | Event::WindowFocusChanged { .. }
+                | Event::WindowFocusTimestampChanged { .. }
+                | Event::WindowUrgencyChanged { .. }
+                | Event::WindowLayoutsChanged { .. }
+        );
+        let overview = match event {
+            Event::OverviewOpenedOrClosed { is_open } => Some(is_open),
+            _ => None,
+        };
+
+        let snapshot = self.tracker.process_event(event, self.filter_workspace);
+
+        if workspaces_changed {
+            let workspaces = self.tracker.workspaces();
+            let outputs: BTreeSet<String> = workspaces.iter().filter_map(|ws| ws.output.clone()).collect();
+
+            if outputs != self.outputs {
+                self.outputs = outputs.clone();
+                self.hub.publish(CompositorEvent::Outputs(outputs))?;
             }
+            self.hub.publish(CompositorEvent::Workspaces(workspaces))?;
         }
+
+        if let Some(is_open) = overview {
+            self.hub.publish(CompositorEvent::Overview { is_open })?;
+        }
+
+        if let Some(snapshot) = snapshot.filter(|_| windows_changed) {
+            self.hub.publish(CompositorEvent::Windows(snapshot))?;
+        }
+
+        Ok(())
     }
 }
 
 #[derive(Debug)]
 struct WindowTracker {
     state: Option<TrackerState>,
+    open_order: Arc<Mutex<OpenOrder>>,
+}
+
+/// Order in which windows were first seen, kept by the client so it survives reconnects.
+#[derive(Debug, Default)]
+struct OpenOrder {
+    opened: std::collections::BTreeMap<u64, u64>,
+    next_opened: u64,
+}
+
+impl OpenOrder {
+    fn update(&mut self, windows: &std::collections::BTreeMap<u64, niri_ipc::Window>) {
+        self.opened.retain(|id, _| windows.contains_key(id));
+        for id in windows.keys() {
+            self.opened.entry(*id).or_insert_with(|| {
+                self.next_opened += 1;
+                self.next_opened
+            });
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -330,8 +793,8 @@ enum TrackerState {
 }
 
 impl WindowTracker {
-    fn new() -> Self {
-        Self { state: None }
+    fn new(open_order: Arc<Mutex<OpenOrder>>) -> Self {
+        Self { state: None, open_order }
     }
 
 	#[tracing::instrument(level = "TRACE", skip(self))]
@@ -426,7 +889,7 @@ impl WindowTracker {
                     }
                 }
             }
-            Event::WorkspaceActivated { id, .. } => {
+            Event::WorkspaceActivated { id, focused } => {
                 if let Some(Ready { workspaces, .. }) = &mut self.state {
                     let activated_output = workspaces.get(&id).and_then(|ws| ws.output.clone());
 
@@ -434,6 +897,30 @@ impl WindowTracker {
                         if ws.output == activated_output {
                             ws.is_active = ws.id == id;
                         }
+                        if focused {
+                            ws.is_focused = ws.id == id;
+                        }
+                    }
+                }
+            }
+            Event::WorkspaceUrgencyChanged { id, urgent } => {
+                if let Some(Ready { workspaces, .. }) = &mut self.state {
+                    if let Some(ws) = workspaces.get_mut(&id) {
+                        ws.is_urgent = urgent;
+                    }
+                }
+            }
+            Event::WindowFocusTimestampChanged { id, focus_timestamp } => {
+                if let Some(Ready { windows, .. }) = &mut self.state {
+                    if let Some(window) = windows.get_mut(&id) {
+                        window.focus_timestamp = focus_timestamp;
+                    }
+                }
+            }
+            Event::WindowUrgencyChanged { id, urgent } => {
+                if let Some(Ready { windows, .. }) = &mut self.state {
+                    if let Some(window) = windows.get_mut(&id) {
+                        window.is_urgent = urgent;
                     }
                 }
             }
@@ -462,6 +949,10 @@ impl WindowTracker {
             _ => {}
         }
 
+        if let Some(Ready { windows, .. }) = &self.state {
+            self.open_order.lock().expect("open order lock").update(windows);
+        }
+
         if let Some(Ready { windows, workspaces, active_per_workspace, last_focused_per_workspace }) = &self.state {
             Some(self.generate_snapshot(windows, workspaces, active_per_workspace, last_focused_per_workspace, filter_workspace))
         } else {
@@ -469,6 +960,14 @@ impl WindowTracker {
         }
     }
 
+    fn workspaces(&self) -> Vec<Workspace> {
+        match &self.state {
+            Some(TrackerState::WorkspacesOnly(workspaces)) => workspaces.clone(),
+            Some(TrackerState::Ready { workspaces, .. }) => workspaces.values().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+
 	fn generate_snapshot(
 		&self,
 		windows: &std::collections::BTreeMap<u64, niri_ipc::Window>,
@@ -542,6 +1041,12 @@ impl WindowTracker {
 				.then_with(|| a.window.id.cmp(&b.window.id))
 		});
 
+        let columns = windows
+            .values()
+            .filter_map(|w| Some(((w.workspace_id?, w.layout.pos_in_scrolling_layout?.0), &w.layout)))
+            .into_group_map();
+        let focused_output = workspaces.values().find(|ws| ws.is_focused).and_then(|ws| ws.output.as_ref());
+
         let active_workspace = workspaces.values().find(|ws| ws.is_active).map(|ws| ws.id);
         let overview_active = active_workspace.and_then(|ws_id| active_per_workspace.get(&ws_id).copied());
         let has_focused = window_workspace_pairs.iter().any(|pair| pair.window.is_focused);
@@ -563,6 +1068,7 @@ impl WindowTracker {
         tracing::info!("snapshot: active_ws={:?}, overview={:?}, last_focused={:?}, highlight={:?}",
             active_workspace, overview_active, last_focused_per_workspace, highlight_window);
 
+        let open_order = self.open_order.lock().expect("open order lock");
         window_workspace_pairs
             .into_iter()
             .map(|pair| {
@@ -571,9 +1077,18 @@ impl WindowTracker {
                     tracing::info!("highlighting window {}", window_copy.id);
                     window_copy.is_focused = true;
                 }
+                let column = window_copy
+                    .layout
+                    .pos_in_scrolling_layout
+                    .and_then(|(column, _)| columns.get(&(pair.workspace.id, column)))
+                    .map(|tiles| ColumnInfo::from_tiles(tiles));
                 WindowInfo {
                     inner: window_copy,
                     output_name: pair.workspace.output.clone(),
+                    workspace: WorkspaceInfo::from(pair.workspace),
+                    opened: open_order.opened.get(&pair.window.id).copied().unwrap_or(u64::MAX),
+                    column,
+                    on_focused_output: pair.workspace.output.is_some() && pair.workspace.output.as_ref() == focused_output,
                 }
             })
             .collect()
@@ -582,16 +1097,121 @@ impl WindowTracker {
 
 pub type WindowSnapshot = Vec<WindowInfo>;
 
+/// Where a dragged button was dropped on another one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropZone {
+    Before,
+    Into,
+    After,
+}
+
+impl DropZone {
+    /// The outer thirds of the target button place the window beside it, the middle stacks it.
+    pub fn at(x: i32, width: i32) -> Self {
+        if x * 3 < width {
+            Self::Before
+        } else if x * 3 >= width * 2 {
+            Self::After
+        } else {
+            Self::Into
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct WindowInfo {
     inner: niri_ipc::Window,
     output_name: Option<String>,
+    workspace: WorkspaceInfo,
+    opened: u64,
+    column: Option<ColumnInfo>,
+    on_focused_output: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceInfo {
+    pub id: u64,
+    pub idx: u8,
+    pub name: Option<String>,
+    pub is_active: bool,
+    pub is_focused: bool,
+}
+
+impl From<&Workspace> for WorkspaceInfo {
+    fn from(workspace: &Workspace) -> Self {
+        Self {
+            id: workspace.id,
+            idx: workspace.idx,
+            name: workspace.name.clone(),
+            is_active: workspace.is_active,
+            is_focused: workspace.is_focused,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnInfo {
+    pub tiles: usize,
+    pub tabbed: bool,
+}
+
+impl ColumnInfo {
+    /// niri doesn't report the column display mode, so `tabbed` is a heuristic: tabs share one
+    /// position while stacked tiles sit below each other. It needs two visible tiles to compare,
+    /// so one-tile columns and columns scrolled out of view always count as stacked.
+    fn from_tiles(tiles: &[&WindowLayout]) -> Self {
+        let visible: Vec<f64> = tiles
+            .iter()
+            .filter_map(|layout| layout.tile_pos_in_workspace_view.map(|(_, y)| y))
+            .collect();
+        let tabbed = visible.len() > 1 && visible.iter().all(|y| (y - visible[0]).abs() < 1.0);
+
+        Self { tiles: tiles.len(), tabbed }
+    }
 }
 
 impl WindowInfo {
     pub fn get_output(&self) -> Option<&str> {
         self.output_name.as_deref()
     }
+
+    pub fn workspace(&self) -> &WorkspaceInfo {
+        &self.workspace
+    }
+
+    pub fn column(&self) -> Option<ColumnInfo> {
+        self.column
+    }
+
+    /// Position in the order windows were first seen by the tracker.
+    pub fn opened(&self) -> u64 {
+        self.opened
+    }
+
+    pub fn has_property(&self, property: WindowProperty) -> bool {
+        match property {
+            WindowProperty::IsFloating => self.is_floating,
+            WindowProperty::IsUrgent => self.is_urgent,
+            WindowProperty::IsStacked => self.column.is_some_and(|column| column.tiles > 1),
+            WindowProperty::IsTabbed => self.column.is_some_and(|column| column.tabbed),
+            WindowProperty::OnFocusedOutput => self.on_focused_output,
+        }
+    }
+
+    /// Whether the action can do anything for this window, e.g. column actions need a tiled window.
+    pub fn supports(&self, action: &WindowAction) -> bool {
+        match action {
+            WindowAction::ExpelWindowFromColumn => self.has_property(WindowProperty::IsStacked),
+            WindowAction::MaximizeColumn
+            | WindowAction::CenterColumn
+            | WindowAction::CenterVisibleColumns
+            | WindowAction::ExpandColumnToAvailableWidth
+            | WindowAction::ConsumeWindowIntoColumn
+            | WindowAction::SwitchPresetColumnWidth
+            | WindowAction::ToggleColumnTabbedDisplay => self.column.is_some(),
+            _ => true,
+        }
+    }
 }
 
 impl Deref for WindowInfo {
@@ -0,0 +1,110 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use niri_ipc::Event;
+use serde::{Deserialize, Serialize};
+use crate::errors::ModuleError;
+
+#[derive(Debug, Deserialize)]
+struct RecordedEvent {
+    timestamp_ms: u64,
+    event: Event,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RecordedLine {
+    Recorded(RecordedEvent),
+    Raw(Event),
+}
+
+#[derive(Debug)]
+pub struct EventRecorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl EventRecorder {
+    pub fn open(path: &Path) -> Result<Self, ModuleError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|source| ModuleError::EventRecording { path: path.to_path_buf(), source })?;
+
+        tracing::info!(path = %path.display(), "recording compositor events");
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub fn record(&mut self, event: &Event) -> Result<(), ModuleError> {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+
+        let mut line = serde_json::to_string(&RecordedEventRef { timestamp_ms, event })
+            .expect("niri events always serialize");
+        line.push('\n');
+
+        self.writer
+            .write_all(line.as_bytes())
+            .and_then(|_| self.writer.flush())
+            .map_err(|source| ModuleError::EventRecording { path: self.path.clone(), source })
+    }
+}
+
+#[derive(Serialize)]
+struct RecordedEventRef<'a> {
+    timestamp_ms: u64,
+    event: &'a Event,
+}
+
+#[derive(Debug)]
+pub struct ReplayEvent {
+    pub delay: Duration,
+    pub event: Event,
+}
+
+pub fn load_recording(path: &Path) -> Result<Vec<ReplayEvent>, ModuleError> {
+    let file = File::open(path).map_err(|source| ModuleError::EventRecording { path: path.to_path_buf(), source })?;
+    parse_recording(BufReader::new(file), path)
+}
+
+pub fn parse_recording(reader: impl BufRead, path: &Path) -> Result<Vec<ReplayEvent>, ModuleError> {
+    let mut events = Vec::new();
+    let mut previous_timestamp = None;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|source| ModuleError::EventRecording { path: path.to_path_buf(), source })?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let parsed = serde_json::from_str(&line).map_err(|source| ModuleError::InvalidRecording {
+            path: path.to_path_buf(),
+            line: index + 1,
+            source,
+        })?;
+
+        let (timestamp, event) = match parsed {
+            RecordedLine::Recorded(RecordedEvent { timestamp_ms, event }) => (Some(timestamp_ms), event),
+            RecordedLine::Raw(event) => (None, event),
+        };
+
+        let delay = match (previous_timestamp, timestamp) {
+            (Some(previous), Some(current)) => Duration::from_millis(u64::saturating_sub(current, previous)),
+            _ => Duration::ZERO,
+        };
+        previous_timestamp = timestamp.or(previous_timestamp);
+
+        events.push(ReplayEvent { delay, event });
+    }
+
+    Ok(events)
+}
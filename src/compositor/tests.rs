@@ -0,0 +1,552 @@
+use std::{sync::mpsc, time::Duration};
+use serde_json::json;
+use super::*;
+use crate::testing::{MockNiri, parse_fixture, window, workspace};
+
+const LAYOUT_ORDERING: &str = include_str!("../../tests/fixtures/layout_ordering.jsonl");
+const OVERVIEW_HIGHLIGHT: &str = include_str!("../../tests/fixtures/overview_highlight.jsonl");
+const MULTI_OUTPUT: &str = include_str!("../../tests/fixtures/multi_output.jsonl");
+
+fn replay(fixture: &str, filter_workspace: bool) -> Vec<Option<WindowSnapshot>> {
+    let mut tracker = WindowTracker::new(Arc::default());
+    parse_fixture(fixture)
+        .into_iter()
+        .map(|event| tracker.process_event(event, filter_workspace))
+        .collect()
+}
+
+fn ids(snapshot: &Option<WindowSnapshot>) -> Vec<u64> {
+    snapshot.as_ref().expect("snapshot").iter().map(|w| w.id).collect()
+}
+
+fn highlighted(snapshot: &Option<WindowSnapshot>) -> Vec<u64> {
+    snapshot.as_ref().expect("snapshot").iter().filter(|w| w.is_focused).map(|w| w.id).collect()
+}
+
+fn client_for(mock: &MockNiri) -> CompositorClient {
+    CompositorClient::with_socket_path(Settings::default(), Some(mock.socket_path().to_path_buf()))
+}
+
+fn collect_events(stream: CompositorEventStream) -> mpsc::Receiver<CompositorEvent> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        while let Some(event) = futures::executor::block_on(stream.next_event()) {
+            if tx.send(event).is_err() {
+                return;
+            }
+        }
+    });
+    rx
+}
+
+fn wait_for<T>(events: &mpsc::Receiver<CompositorEvent>, mut select: impl FnMut(CompositorEvent) -> Option<T>) -> T {
+    loop {
+        let event = events.recv_timeout(Duration::from_secs(5)).expect("timed out waiting for compositor event");
+        if let Some(value) = select(event) {
+            return value;
+        }
+    }
+}
+
+#[test]
+fn tracker_waits_for_windows_and_workspaces() {
+    let snapshots = replay(LAYOUT_ORDERING, false);
+
+    assert!(snapshots[0].is_none());
+    assert!(snapshots[1].is_some());
+}
+
+#[test]
+fn tracker_orders_by_workspace_column_and_tile() {
+    let snapshots = replay(LAYOUT_ORDERING, false);
+
+    assert_eq!(ids(&snapshots[1]), [13, 12, 11, 15, 10]);
+}
+
+#[test]
+fn floating_window_follows_last_focused_tiled_window() {
+    let snapshots = replay(LAYOUT_ORDERING, false);
+
+    assert_eq!(ids(&snapshots[3]), [13, 12, 15, 11, 10]);
+    assert_eq!(highlighted(&snapshots[3]), [15]);
+
+    assert_eq!(ids(&snapshots[4]), [12, 15, 11, 13, 10]);
+}
+
+#[test]
+fn floating_window_falls_back_to_last_column_when_anchor_closes() {
+    let snapshots = replay(LAYOUT_ORDERING, false);
+
+    assert_eq!(ids(&snapshots[5]), [11, 13, 15, 10]);
+}
+
+#[test]
+fn overview_highlight_follows_active_window() {
+    let snapshots = replay(OVERVIEW_HIGHLIGHT, false);
+
+    assert_eq!(highlighted(&snapshots[2]), [20]);
+    assert_eq!(highlighted(&snapshots[3]), [21]);
+    assert_eq!(highlighted(&snapshots[4]), [22]);
+}
+
+#[test]
+fn current_workspace_filter_is_per_output() {
+    let snapshots = replay(MULTI_OUTPUT, true);
+
+    assert_eq!(ids(&snapshots[1]), [30, 32]);
+    assert_eq!(ids(&snapshots[2]), [32, 31]);
+
+    let outputs: Vec<_> = snapshots[2].as_ref().unwrap().iter().map(|w| w.get_output()).collect();
+    assert_eq!(outputs, [Some("HDMI-A-1"), Some("DP-1")]);
+}
+
+#[test]
+fn snapshot_reports_column_and_output_properties() {
+    let at = |mut window: niri_ipc::Window, y: f64| {
+        window.layout.tile_pos_in_workspace_view = Some((0.0, y));
+        window
+    };
+    let mut urgent = window(5, 2, None);
+    urgent.is_urgent = true;
+
+    let mut focused_ws = workspace(1, 1, "DP-1", true);
+    focused_ws.is_focused = true;
+    let mut tracker = WindowTracker::new(Arc::default());
+    let workspaces = vec![focused_ws, workspace(2, 2, "HDMI-A-1", true)];
+    tracker.process_event(Event::WorkspacesChanged { workspaces }, false);
+    let snapshot = tracker
+        .process_event(
+            Event::WindowsChanged {
+                windows: vec![
+                    at(window(1, 1, Some((1, 1))), 0.0),
+                    at(window(2, 1, Some((1, 2))), 300.0),
+                    window(3, 1, Some((2, 1))),
+                    at(window(4, 2, Some((1, 1))), 20.0),
+                    at(window(6, 2, Some((1, 2))), 20.0),
+                    urgent,
+                ],
+            },
+            false,
+        )
+        .expect("snapshot");
+
+    let with = |property| snapshot.iter().filter(|w| w.has_property(property)).map(|w| w.id).collect::<Vec<_>>();
+    assert_eq!(with(WindowProperty::IsStacked), [1, 2, 4, 6]);
+    assert_eq!(with(WindowProperty::IsTabbed), [4, 6]);
+    assert_eq!(with(WindowProperty::IsFloating), [5]);
+    assert_eq!(with(WindowProperty::IsUrgent), [5]);
+    assert_eq!(with(WindowProperty::OnFocusedOutput), [1, 2, 3]);
+
+    let supported = |action| snapshot.iter().filter(|w| w.supports(&action)).map(|w| w.id).collect::<Vec<_>>();
+    assert_eq!(supported(WindowAction::ExpelWindowFromColumn), [1, 2, 4, 6]);
+    assert_eq!(supported(WindowAction::MaximizeColumn), [1, 2, 3, 4, 6]);
+    assert_eq!(supported(WindowAction::ToggleWindowFloating), [1, 2, 3, 4, 6, 5]);
+}
+
+#[test]
+fn tabbed_columns_need_two_visible_tiles() {
+    let layout = |y: Option<f64>| {
+        let mut layout = window(1, 1, Some((1, 1))).layout;
+        layout.tile_pos_in_workspace_view = y.map(|y| (0.0, y));
+        layout
+    };
+    let column = |tiles: &[Option<f64>]| {
+        let layouts: Vec<_> = tiles.iter().map(|y| layout(*y)).collect();
+        ColumnInfo::from_tiles(&layouts.iter().collect::<Vec<_>>())
+    };
+
+    assert_eq!(column(&[Some(20.0), Some(20.0)]), ColumnInfo { tiles: 2, tabbed: true });
+    assert_eq!(column(&[Some(20.0)]), ColumnInfo { tiles: 1, tabbed: false });
+    assert_eq!(column(&[None, None]), ColumnInfo { tiles: 2, tabbed: false });
+    assert_eq!(column(&[Some(20.0), None, None]), ColumnInfo { tiles: 3, tabbed: false });
+}
+
+#[test]
+fn focus_timestamps_update_the_snapshot() {
+    let mut tracker = WindowTracker::new(Arc::default());
+    tracker.process_event(Event::WorkspacesChanged { workspaces: vec![workspace(1, 1, "DP-1", true)] }, false);
+    tracker.process_event(Event::WindowsChanged { windows: vec![window(1, 1, Some((1, 1)))] }, false);
+
+    let focus_timestamp = Some(niri_ipc::Timestamp { secs: 12, nanos: 5 });
+    let snapshot = tracker.process_event(Event::WindowFocusTimestampChanged { id: 1, focus_timestamp }, false);
+
+    assert_eq!(snapshot.expect("snapshot")[0].focus_timestamp, focus_timestamp);
+}
+
+#[test]
+fn windows_remember_when_they_were_first_seen() {
+    let mut tracker = WindowTracker::new(Arc::default());
+    tracker.process_event(Event::WorkspacesChanged { workspaces: vec![workspace(1, 1, "DP-1", true)] }, false);
+    tracker.process_event(Event::WindowsChanged { windows: vec![window(2, 1, Some((2, 1))), window(5, 1, Some((1, 1)))] }, false);
+    tracker.process_event(Event::WindowClosed { id: 2 }, false);
+
+    let snapshot = tracker
+        .process_event(Event::WindowOpenedOrChanged { window: window(3, 1, Some((2, 1))) }, false)
+        .expect("snapshot");
+
+    let opened: Vec<_> = snapshot.iter().map(|w| (w.id, w.opened())).collect();
+    assert_eq!(opened, [(5, 2), (3, 3)]);
+}
+
+#[test]
+fn open_order_outlives_the_tracker() {
+    let open_order = Arc::default();
+    let workspaces = vec![workspace(1, 1, "DP-1", true)];
+
+    let mut tracker = WindowTracker::new(Arc::clone(&open_order));
+    tracker.process_event(Event::WorkspacesChanged { workspaces: workspaces.clone() }, false);
+    tracker.process_event(Event::WindowsChanged { windows: vec![window(5, 1, Some((1, 1)))] }, false);
+    tracker.process_event(Event::WindowOpenedOrChanged { window: window(2, 1, Some((2, 1))) }, false);
+
+    let mut reconnected = WindowTracker::new(open_order);
+    reconnected.process_event(Event::WorkspacesChanged { workspaces }, false);
+    let snapshot = reconnected
+        .process_event(Event::WindowsChanged { windows: vec![window(2, 1, Some((2, 1))), window(5, 1, Some((1, 1)))] }, false)
+        .expect("snapshot");
+
+    let mut opened: Vec<_> = snapshot.iter().map(|w| (w.id, w.opened())).collect();
+    opened.sort();
+    assert_eq!(opened, [(2, 2), (5, 1)]);
+}
+
+#[test]
+fn event_stream_publishes_full_state_and_replayed_events() {
+    let mock = MockNiri::start()
+        .with_windows(vec![window(10, 2, Some((1, 1)))])
+        .with_workspaces(vec![workspace(1, 1, "DP-1", true), workspace(2, 2, "DP-1", false)])
+        .with_events(parse_fixture(LAYOUT_ORDERING));
+    let events = collect_events(client_for(&mock).subscribe_events());
+
+    wait_for(&events, |event| matches!(event, CompositorEvent::Connection { connected: true }).then_some(()));
+    let outputs = wait_for(&events, |event| match event {
+        CompositorEvent::Outputs(outputs) => Some(outputs),
+        _ => None,
+    });
+    assert_eq!(outputs.into_iter().collect::<Vec<_>>(), ["DP-1"]);
+
+    let first = wait_for(&events, |event| match event {
+        CompositorEvent::Windows(snapshot) => Some(snapshot),
+        _ => None,
+    });
+    assert_eq!(first.iter().map(|w| w.id).collect::<Vec<_>>(), [10]);
+
+    wait_for(&events, |event| match event {
+        CompositorEvent::Windows(snapshot) if snapshot.iter().map(|w| w.id).eq([11, 13, 15, 10]) => Some(()),
+        _ => None,
+    });
+}
+
+#[test]
+fn current_workspaces_follow_the_event_stream() {
+    let mock = MockNiri::start()
+        .with_windows(vec![window(10, 1, Some((1, 1)))])
+        .with_workspaces(vec![workspace(1, 1, "DP-1", true), workspace(2, 1, "HDMI-A-1", true)]);
+    let client = client_for(&mock);
+    assert!(client.current_workspaces().is_empty());
+
+    let events = collect_events(client.subscribe_events());
+    wait_for(&events, |event| matches!(event, CompositorEvent::Workspaces(_)).then_some(()));
+
+    let mut ids: Vec<_> = client.current_workspaces().iter().map(|ws| ws.id).collect();
+    ids.sort();
+    assert_eq!(ids, [1, 2]);
+}
+
+#[test]
+fn event_stream_reconnects_after_drop() {
+    let mock = MockNiri::start()
+        .with_windows(vec![window(10, 1, Some((1, 1)))])
+        .with_workspaces(vec![workspace(1, 1, "DP-1", true)]);
+    let events = collect_events(client_for(&mock).subscribe_events());
+
+    wait_for(&events, |event| matches!(event, CompositorEvent::Connection { connected: true }).then_some(()));
+    mock.drop_event_streams();
+
+    wait_for(&events, |event| matches!(event, CompositorEvent::Connection { connected: false }).then_some(()));
+    wait_for(&events, |event| matches!(event, CompositorEvent::Connection { connected: true }).then_some(()));
+    let snapshot = wait_for(&events, |event| match event {
+        CompositorEvent::Windows(snapshot) => Some(snapshot),
+        _ => None,
+    });
+    assert_eq!(snapshot.iter().map(|w| w.id).collect::<Vec<_>>(), [10]);
+}
+
+#[test]
+fn requests_share_one_connection() {
+    let mock = MockNiri::start().with_windows(vec![window(1, 1, Some((1, 1)))]);
+    let client = client_for(&mock);
+
+    client.focus_window(1).unwrap();
+    client.close_window(1).unwrap();
+    assert_eq!(client.query_windows().unwrap().len(), 1);
+
+    assert_eq!(mock.connections(), 1);
+    assert_eq!(mock.actions(), [json!({"FocusWindow": {"id": 1}}), json!({"CloseWindow": {"id": 1}})]);
+}
+
+#[test]
+fn compositor_error_is_reported() {
+    let mock = MockNiri::start().failing_action("CloseWindow");
+    let client = client_for(&mock);
+
+    assert!(matches!(client.close_window(1), Err(ModuleError::CompositorReply(_))));
+}
+
+#[test]
+fn drop_zones_pick_the_column_index() {
+    let mut focused = window(9, 1, Some((5, 1)));
+    focused.is_focused = true;
+    let mock = MockNiri::start().with_windows(vec![window(1, 1, Some((1, 1))), window(2, 1, Some((3, 1))), focused]);
+    let client = client_for(&mock);
+
+    client.drop_window(1, 2, DropZone::Before).unwrap();
+    client.drop_window(1, 2, DropZone::After).unwrap();
+    client.drop_window(2, 1, DropZone::Before).unwrap();
+
+    let moves: Vec<_> = mock.actions().into_iter().filter(|action| action.get("MoveColumnToIndex").is_some()).collect();
+    assert_eq!(
+        moves,
+        [
+            json!({"MoveColumnToIndex": {"index": 2}}),
+            json!({"MoveColumnToIndex": {"index": 3}}),
+            json!({"MoveColumnToIndex": {"index": 1}}),
+        ]
+    );
+    assert_eq!(mock.actions().last(), Some(&json!({"FocusWindow": {"id": 9}})));
+}
+
+#[test]
+fn dropping_into_a_column_stacks_the_window() {
+    let windows = vec![window(3, 1, Some((1, 2))), window(4, 1, Some((1, 1))), window(5, 1, Some((2, 1)))];
+    let mock = MockNiri::start().with_windows(windows);
+    let client = client_for(&mock);
+
+    client.drop_window(3, 5, DropZone::Into).unwrap();
+
+    assert_eq!(
+        mock.actions(),
+        [
+            json!({"FocusWindow": {"id": 3}}),
+            json!({"ExpelWindowFromColumn": {}}),
+            json!({"MoveColumnToIndex": {"index": 3}}),
+            json!({"ConsumeOrExpelWindowLeft": {"id": 3}}),
+        ]
+    );
+}
+
+#[test]
+fn drops_on_other_workspaces_move_the_window_by_workspace_id() {
+    let mock = MockNiri::start().with_windows(vec![window(1, 1, Some((5, 1))), window(2, 7, Some((3, 1)))]);
+    let client = client_for(&mock);
+
+    client.drop_window(1, 2, DropZone::Before).unwrap();
+
+    assert_eq!(
+        mock.actions(),
+        [
+            json!({"MoveWindowToWorkspace": {"window_id": 1, "reference": {"Id": 7}, "focus": false}}),
+            json!({"FocusWindow": {"id": 1}}),
+            json!({"MoveColumnToIndex": {"index": 3}}),
+        ]
+    );
+}
+
+#[test]
+fn impossible_drops_are_refused() {
+    let mock = MockNiri::start().with_windows(vec![window(1, 1, Some((1, 1))), window(2, 1, None)]);
+    let client = client_for(&mock);
+
+    assert!(matches!(client.drop_window(1, 2, DropZone::Into), Err(ModuleError::InvalidDrop { .. })));
+    assert!(matches!(client.drop_window(8, 1, DropZone::Into), Err(ModuleError::UnknownWindow(8))));
+    assert!(mock.actions().is_empty());
+}
+
+#[test]
+fn drop_zones_split_buttons_in_thirds() {
+    let zones: Vec<_> = [0, 33, 34, 66, 67, 99].into_iter().map(|x| DropZone::at(x, 100)).collect();
+
+    assert_eq!(zones, [DropZone::Before, DropZone::Before, DropZone::Into, DropZone::Into, DropZone::After, DropZone::After]);
+}
+
+#[test]
+fn every_window_action_issues_expected_requests() {
+    let focus = json!({"FocusWindow": {"id": 7}});
+    let cases = [
+        ("none", vec![]),
+        ("menu", vec![]),
+        ("focus-window", vec![focus.clone()]),
+        ("close-window", vec![json!({"CloseWindow": {"id": 7}})]),
+        ("maximize-column", vec![focus.clone(), json!({"MaximizeColumn": {}})]),
+        ("maximize-window-to-edges", vec![focus.clone(), json!({"MaximizeWindowToEdges": {"id": 7}})]),
+        ("center-column", vec![focus.clone(), json!({"CenterColumn": {}})]),
+        ("center-window", vec![json!({"CenterWindow": {"id": 7}})]),
+        ("center-visible-columns", vec![focus.clone(), json!({"CenterVisibleColumns": {}})]),
+        ("expand-column-to-available-width", vec![focus.clone(), json!({"ExpandColumnToAvailableWidth": {}})]),
+        ("fullscreen-window", vec![json!({"FullscreenWindow": {"id": 7}})]),
+        ("toggle-windowed-fullscreen", vec![json!({"ToggleWindowedFullscreen": {"id": 7}})]),
+        ("toggle-window-floating", vec![json!({"ToggleWindowFloating": {"id": 7}})]),
+        ("consume-window-into-column", vec![focus.clone(), json!({"ConsumeWindowIntoColumn": {}})]),
+        ("expel-window-from-column", vec![focus.clone(), json!({"ExpelWindowFromColumn": {}})]),
+        ("reset-window-height", vec![focus.clone(), json!({"ResetWindowHeight": {"id": null}})]),
+        ("switch-preset-column-width", vec![focus.clone(), json!({"SwitchPresetColumnWidth": {}})]),
+        ("switch-preset-window-height", vec![focus.clone(), json!({"SwitchPresetWindowHeight": {"id": null}})]),
+        ("move-window-to-workspace-down", vec![focus.clone(), json!({"MoveWindowToWorkspaceDown": {"focus": false}})]),
+        ("move-window-to-workspace-up", vec![focus.clone(), json!({"MoveWindowToWorkspaceUp": {"focus": false}})]),
+        ("move-window-to-monitor-left", vec![focus.clone(), json!({"MoveWindowToMonitorLeft": {}})]),
+        ("move-window-to-monitor-right", vec![focus.clone(), json!({"MoveWindowToMonitorRight": {}})]),
+        ("toggle-column-tabbed-display", vec![focus.clone(), json!({"ToggleColumnTabbedDisplay": {}})]),
+        ("focus-workspace-previous", vec![focus.clone(), json!({"FocusWorkspacePrevious": {}})]),
+    ];
+
+    let mock = MockNiri::start();
+    let client = client_for(&mock);
+
+    for (name, expected) in cases {
+        let action: WindowAction = serde_json::from_value(json!(name)).expect(name);
+        mock.clear_actions();
+
+        client.perform_action(7, &action).unwrap_or_else(|e| panic!("{name}: {e}"));
+
+        assert_eq!(mock.actions(), expected, "{name}");
+    }
+}
+
+fn settings(value: serde_json::Value) -> Settings {
+    serde_json::from_value(value).expect("settings")
+}
+
+fn last_snapshot_ids(events: &mpsc::Receiver<CompositorEvent>, expected: &[u64]) {
+    wait_for(events, |event| match event {
+        CompositorEvent::Windows(snapshot) if snapshot.iter().map(|w| w.id).eq(expected.iter().copied()) => Some(()),
+        _ => None,
+    });
+}
+
+#[test]
+fn recorded_events_replay_to_the_same_snapshot() {
+    let mock = MockNiri::start()
+        .with_windows(vec![window(10, 2, Some((1, 1)))])
+        .with_workspaces(vec![workspace(1, 1, "DP-1", true), workspace(2, 2, "DP-1", false)])
+        .with_events(parse_fixture(LAYOUT_ORDERING));
+    let recording = mock.socket_path().with_file_name("events.jsonl");
+
+    let client = CompositorClient::with_socket_path(
+        settings(json!({ "debug_record_events": recording })),
+        Some(mock.socket_path().to_path_buf()),
+    );
+    let events = collect_events(client.subscribe_events());
+    last_snapshot_ids(&events, &[11, 13, 15, 10]);
+
+    let recorded = recording::load_recording(&recording).unwrap();
+    assert_eq!(recorded.len(), 2 + parse_fixture(LAYOUT_ORDERING).len());
+    assert!(recorded[0].delay.is_zero());
+
+    let replayed = CompositorClient::create(settings(json!({ "debug_replay_events": recording })));
+    let events = collect_events(replayed.subscribe_events());
+    wait_for(&events, |event| matches!(event, CompositorEvent::Connection { connected: true }).then_some(()));
+    last_snapshot_ids(&events, &[11, 13, 15, 10]);
+}
+
+#[test]
+fn replay_accepts_raw_event_lines() {
+    let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/multi_output.jsonl");
+    let client = CompositorClient::create(settings(json!({
+        "debug_replay_events": fixture,
+        "only_current_workspace": true,
+    })));
+    let events = collect_events(client.subscribe_events());
+
+    last_snapshot_ids(&events, &[32, 31]);
+}
+
+#[test]
+fn invalid_recording_reports_line() {
+    let recording = "{\"timestamp_ms\": 1, \"event\": {\"OverviewOpenedOrClosed\": {\"is_open\": true}}}\n\nnot json\n";
+    let result = recording::parse_recording(recording.as_bytes(), Path::new("events.jsonl"));
+
+    assert!(matches!(result, Err(ModuleError::InvalidRecording { line: 3, .. })));
+}
+
+#[test]
+fn replay_delays_follow_recorded_timestamps() {
+    let recording = concat!(
+        "{\"timestamp_ms\": 1000, \"event\": {\"OverviewOpenedOrClosed\": {\"is_open\": true}}}\n",
+        "{\"OverviewOpenedOrClosed\": {\"is_open\": false}}\n",
+        "{\"timestamp_ms\": 1250, \"event\": {\"OverviewOpenedOrClosed\": {\"is_open\": true}}}\n",
+    );
+    let events = recording::parse_recording(recording.as_bytes(), Path::new("events.jsonl")).unwrap();
+    let delays: Vec<_> = events.iter().map(|e| e.delay).collect();
+
+    assert_eq!(delays, [Duration::ZERO, Duration::ZERO, Duration::from_millis(250)]);
+}
+
+#[test]
+fn niri_actions_target_the_clicked_window() {
+    let focus = json!({"FocusWindow": {"id": 7}});
+    let cases = [
+        (
+            json!({"SetWindowWidth": {"change": {"AdjustProportion": 10.0}}}),
+            vec![json!({"SetWindowWidth": {"id": 7, "change": {"AdjustProportion": 10.0}}})],
+        ),
+        (
+            json!({"MoveWindowToWorkspace": {"reference": {"Name": "chat"}, "focus": false}}),
+            vec![json!({"MoveWindowToWorkspace": {"window_id": 7, "reference": {"Name": "chat"}, "focus": false}})],
+        ),
+        (json!({"ToggleWindowUrgent": {}}), vec![json!({"ToggleWindowUrgent": {"id": 7}})]),
+        (json!({"CloseWindow": {"id": 3}}), vec![json!({"CloseWindow": {"id": 3}})]),
+        (json!("MoveColumnToFirst"), vec![focus.clone(), json!({"MoveColumnToFirst": {}})]),
+        (
+            json!({"SetColumnWidth": {"change": {"AdjustProportion": 10.0}}}),
+            vec![focus.clone(), json!({"SetColumnWidth": {"change": {"AdjustProportion": 10.0}}})],
+        ),
+    ];
+
+    let mock = MockNiri::start();
+    let client = client_for(&mock);
+
+    for (config, expected) in cases {
+        let action: WindowAction = serde_json::from_value(json!({ "niri": config })).expect("niri action");
+        mock.clear_actions();
+
+        client.perform_action(7, &action).unwrap_or_else(|e| panic!("{config}: {e}"));
+
+        assert_eq!(mock.actions(), expected, "{config}");
+    }
+}
+
+#[test]
+fn unknown_niri_action_is_rejected_in_config() {
+    assert!(serde_json::from_value::<WindowAction>(json!({"niri": {"NoSuchAction": {}}})).is_err());
+    assert!(serde_json::from_value::<WindowAction>(json!({"niri": {"SetWindowWidth": {}}})).is_err());
+}
+
+#[test]
+fn exec_action_receives_window_details_as_arguments() {
+    let mut target = window(9, 2, Some((1, 1)));
+    target.title = Some("a title; with $(shell) chars".to_string());
+    let mock = MockNiri::start()
+        .with_windows(vec![target])
+        .with_workspaces(vec![workspace(2, 1, "HDMI-A-1", true)]);
+    let output_file = mock.socket_path().with_file_name("exec.out");
+
+    let command = format!(
+        "sh -c 'printf \"%s\\n\" \"$@\" > {}' sh {{id}} {{app_id}} {{title}} {{pid}} {{workspace_id}} {{output}}",
+        output_file.display()
+    );
+    let action: WindowAction = serde_json::from_value(json!({ "exec": command })).unwrap();
+    client_for(&mock).perform_action(9, &action).unwrap();
+
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    let written = loop {
+        match std::fs::read_to_string(&output_file) {
+            Ok(content) if content.lines().count() == 6 => break content,
+            _ if std::time::Instant::now() < deadline => std::thread::sleep(Duration::from_millis(10)),
+            _ => panic!("command did not write its arguments"),
+        }
+    };
+
+    assert_eq!(
+        written.lines().collect::<Vec<_>>(),
+        ["9", "app.9", "a title; with $(shell) chars", "1009", "2", "HDMI-A-1"]
+    );
+}
@@ -14,8 +14,59 @@ pub enum ModuleError {
         actual: Box<niri_ipc::Response>,
     },
 
-    #[error("window snapshot channel closed")]
-    SnapshotChannelClosed,
+    #[error("compositor event channel closed")]
+    EventChannelClosed,
+
+    #[error("window {0} no longer exists")]
+    UnknownWindow(u64),
+
+    #[error("can't drop window {window_id} there: {reason}")]
+    InvalidDrop {
+        window_id: u64,
+        reason: &'static str,
+    },
+
+    #[error("compositor action queue closed")]
+    ActionQueueClosed,
+
+    #[error("invalid niri action: {0}")]
+    InvalidNiriAction(#[source] serde_json::Error),
+
+    #[error("failed to spawn {program:?}: {source}")]
+    CommandSpawn {
+        program: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("event recording {path:?}: {source}")]
+    EventRecording {
+        path: std::path::PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("state file {path:?}: {source}")]
+    StateFile {
+        path: std::path::PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("invalid state file {path:?}: {source}")]
+    InvalidStateFile {
+        path: std::path::PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("invalid event recording {path:?} at line {line}: {source}")]
+    InvalidRecording {
+        path: std::path::PathBuf,
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
 }
 
 impl ModuleError {
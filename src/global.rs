@@ -1,14 +1,16 @@
 use std::sync::Arc;
-use async_channel::Sender;
+use async_channel::{Receiver, Sender};
 use futures::{Stream, StreamExt};
-use niri_ipc::Workspace;
 use waybar_cffi::gtk::glib;
 use crate::{
-    compositor::{CompositorClient, WindowSnapshot},
-    errors::ModuleError,
+    actions::ActionQueue,
+    compositor::{CompositorClient, CompositorEvent, CompositorEventStream},
     icons::IconResolver,
     notifications::{self, NotificationData},
+    order::BarOrder,
+    pinned::PinnedApps,
     settings::Settings,
+    state,
 };
 
 #[derive(Debug, Clone)]
@@ -19,13 +21,21 @@ struct StateInner {
     settings: Settings,
     icon_resolver: IconResolver,
     compositor: CompositorClient,
+    actions: ActionQueue,
+    pinned: PinnedApps,
+    bar_order: BarOrder,
 }
 
 impl SharedState {
     pub fn create(settings: Settings) -> Self {
+        let compositor = CompositorClient::create(settings.clone());
+
         Self(Arc::new(StateInner {
-            compositor: CompositorClient::create(settings.clone()),
+            actions: ActionQueue::start(compositor.clone()),
+            compositor,
             icon_resolver: IconResolver::new(),
+            pinned: PinnedApps::load(settings.pinned(), state::state_file("pinned.json")),
+            bar_order: BarOrder::load(state::state_file("order.json")),
             settings,
         }))
     }
@@ -42,34 +52,42 @@ impl SharedState {
         &self.0.compositor
     }
 
-    pub fn create_event_stream(&self) -> Result<impl Stream<Item = EventMessage>, ModuleError> {
+    pub fn actions(&self) -> &ActionQueue {
+        &self.0.actions
+    }
+
+    pub fn pinned(&self) -> &PinnedApps {
+        &self.0.pinned
+    }
+
+    pub fn bar_order(&self) -> &BarOrder {
+        &self.0.bar_order
+    }
+
+    pub fn create_event_stream(&self) -> impl Stream<Item = EventMessage> {
         let (tx, rx) = async_channel::unbounded();
 
         if self.settings().notifications_enabled() {
             glib::spawn_future_local(forward_notifications(tx.clone()));
         }
 
-        glib::spawn_future_local(forward_window_updates(tx.clone(), self.compositor().create_window_stream()));
+        glib::spawn_future_local(forward_pin_changes(tx.clone(), self.pinned().changes()));
+        glib::spawn_future_local(forward_drops(tx.clone(), self.bar_order().drops()));
+        glib::spawn_future_local(forward_compositor_events(tx, self.compositor().subscribe_events()));
 
-        let mut workspace_stream_delay = Some((tx, self.compositor().create_workspace_stream()?));
-
-        Ok(async_stream::stream! {
+        async_stream::stream! {
             while let Ok(event) = rx.recv().await {
-                if let Some((tx, stream)) = workspace_stream_delay.take() {
-                    if matches!(&event, EventMessage::Workspaces(_)) {
-                        glib::spawn_future_local(forward_workspace_changes(tx, stream));
-                    }
-                }
                 yield event;
             }
-        })
+        }
     }
 }
 
 pub enum EventMessage {
     Notification(Box<NotificationData>),
-    WindowUpdate(WindowSnapshot),
-    Workspaces(()),
+    Compositor(CompositorEvent),
+    PinsChanged,
+    BarReordered,
 }
 
 async fn forward_notifications(tx: Sender<EventMessage>) {
@@ -81,19 +99,26 @@ async fn forward_notifications(tx: Sender<EventMessage>) {
     }
 }
 
-async fn forward_window_updates(tx: Sender<EventMessage>, stream: crate::compositor::WindowEventStream) {
-    while let Some(snapshot) = stream.next_snapshot().await {
-        if let Err(e) = tx.send(EventMessage::WindowUpdate(snapshot)).await {
-            tracing::error!(%e, "failed to forward window update");
+async fn forward_compositor_events(tx: Sender<EventMessage>, stream: CompositorEventStream) {
+    while let Some(event) = stream.next_event().await {
+        if let Err(e) = tx.send(EventMessage::Compositor(event)).await {
+            tracing::error!(%e, "failed to forward compositor event");
+        }
+    }
+}
+
+async fn forward_pin_changes(tx: Sender<EventMessage>, changes: Receiver<()>) {
+    while changes.recv().await.is_ok() {
+        if let Err(e) = tx.send(EventMessage::PinsChanged).await {
+            tracing::error!(%e, "failed to forward pinned apps change");
         }
     }
 }
 
-async fn forward_workspace_changes(tx: Sender<EventMessage>, stream: impl Stream<Item = Vec<Workspace>>) {
-    let mut workspace_stream = Box::pin(stream);
-    while workspace_stream.next().await.is_some() {
-        if let Err(e) = tx.send(EventMessage::Workspaces(())).await {
-            tracing::error!(%e, "failed to forward workspace change");
+async fn forward_drops(tx: Sender<EventMessage>, drops: Receiver<()>) {
+    while drops.recv().await.is_ok() {
+        if let Err(e) = tx.send(EventMessage::BarReordered).await {
+            tracing::error!(%e, "failed to forward taskbar reorder");
         }
     }
 }
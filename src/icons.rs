@@ -9,7 +9,13 @@ use waybar_cffi::gtk::{
 };
 
 #[derive(Debug, Clone, Default)]
-pub struct IconResolver(Arc<Mutex<HashMap<String, PathBuf>>>);
+pub struct IconResolver(Arc<Mutex<HashMap<String, AppEntry>>>);
+
+#[derive(Debug, Clone, Default)]
+struct AppEntry {
+    icon: Option<PathBuf>,
+    desktop_file: Option<PathBuf>,
+}
 
 impl IconResolver {
     pub fn new() -> Self {
@@ -18,36 +24,53 @@ impl IconResolver {
 
     #[tracing::instrument(level = "TRACE", ret)]
     pub fn resolve(&self, app_id: &str) -> Option<PathBuf> {
+        self.lookup(app_id).icon
+    }
+
+    /// The desktop entry `resolve` took the icon from, for launching the app and its actions.
+    pub fn desktop_file(&self, app_id: &str) -> Option<PathBuf> {
+        self.lookup(app_id).desktop_file
+    }
+
+    fn lookup(&self, app_id: &str) -> AppEntry {
         let mut cache = self.0.lock().expect("icon resolver lock");
 
         if !cache.contains_key(app_id) {
-            if let Some(path) = search_for_icon(app_id) {
-                cache.insert(app_id.to_string(), path);
+            let entry = search_for_app(app_id);
+            if entry.icon.is_some() || entry.desktop_file.is_some() {
+                cache.insert(app_id.to_string(), entry);
             }
         }
 
-        cache.get(app_id).cloned()
+        cache.get(app_id).cloned().unwrap_or_default()
     }
 }
 
-fn search_for_icon(app_id: &str) -> Option<PathBuf> {
+fn search_for_app(app_id: &str) -> AppEntry {
+    let mut first_match = None;
+    let mut check = |info: DesktopAppInfo| {
+        let icon = extract_icon_path(&info);
+        let desktop_file = info.filename();
+        if icon.is_none() {
+            first_match.get_or_insert(desktop_file);
+            return None;
+        }
+        Some(AppEntry { icon, desktop_file })
+    };
+
     for directory in DATA_DIRECTORIES.iter() {
         for suffix in ["", ".desktop"] {
             let app_path = directory.join(format!("applications/{app_id}{suffix}"));
-            if let Some(info) = DesktopAppInfo::from_filename(&app_path) {
-                if let Some(path) = extract_icon_path(&info) {
-                    return Some(path);
-                }
+            if let Some(entry) = DesktopAppInfo::from_filename(&app_path).and_then(&mut check) {
+                return entry;
             }
         }
 
         for prefix in ["applications/kde/", "applications/org.kde."] {
             for suffix in ["", ".desktop"] {
                 let kde_path = directory.join(format!("{prefix}{app_id}{suffix}"));
-                if let Some(info) = DesktopAppInfo::from_filename(&kde_path) {
-                    if let Some(path) = extract_icon_path(&info) {
-                        return Some(path);
-                    }
+                if let Some(entry) = DesktopAppInfo::from_filename(&kde_path).and_then(&mut check) {
+                    return entry;
                 }
             }
         }
@@ -56,15 +79,16 @@ fn search_for_icon(app_id: &str) -> Option<PathBuf> {
     let search_results = DesktopAppInfo::search(app_id);
     for candidates in search_results.into_iter() {
         for candidate in candidates {
-            if let Some(info) = DesktopAppInfo::new(&candidate) {
-                if let Some(path) = extract_icon_path(&info) {
-                    return Some(path);
-                }
+            if let Some(entry) = DesktopAppInfo::new(&candidate).and_then(&mut check) {
+                return entry;
             }
         }
     }
 
-    query_icon_theme(app_id)
+    AppEntry {
+        icon: query_icon_theme(app_id),
+        desktop_file: first_match.flatten(),
+    }
 }
 
 fn query_icon_theme(icon_name: &str) -> Option<PathBuf> {
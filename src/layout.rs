@@ -0,0 +1,182 @@
+use std::{cmp::Ordering, collections::HashMap};
+use crate::{pinned::matches_app, settings::SortOrder};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, PartialEq)]
+pub enum Slot<'a, W> {
+    Launcher(&'a str),
+    Window(W),
+    Group(Vec<W>),
+    /// Starts the workspace section of the given window.
+    Section(W),
+    /// Windows sharing one niri column.
+    Column(Vec<W>),
+}
+
+/// What `sort` needs to know about a window.
+#[derive(Debug, Clone, Copy)]
+pub struct SortKeys<'a> {
+    pub id: u64,
+    pub workspace: (u8, Option<&'a str>),
+    pub app_id: Option<&'a str>,
+    pub opened: u64,
+    pub focused_at: Option<(u64, u32)>,
+    /// Place in the saved manual order, if the window has one.
+    pub saved: Option<usize>,
+}
+
+/// Reorders windows given in niri's layout order. Unless sorting by layout, `by_workspace`
+/// keeps workspaces as the primary key. `SortOrder::Manual` puts windows with a saved place
+/// first, then follows `previous`, the order of the last update. Ties keep the layout order.
+pub fn sort<W>(windows: &mut [W], order: SortOrder, by_workspace: bool, previous: &[u64], keys: impl Fn(&W) -> SortKeys<'_>) {
+    if order == SortOrder::Layout {
+        return;
+    }
+
+    let previous: HashMap<u64, usize> = previous.iter().enumerate().map(|(index, id)| (*id, index)).collect();
+    let app_rank = |app_id: Option<&str>| (app_id.is_none(), app_id.map(str::to_lowercase));
+    let manual_rank = |keys: &SortKeys| {
+        (keys.saved.is_none(), keys.saved, previous.get(&keys.id).copied().unwrap_or(usize::MAX))
+    };
+
+    windows.sort_by(|a, b| {
+        let (a, b) = (keys(a), keys(b));
+        let section = if by_workspace { a.workspace.cmp(&b.workspace) } else { Ordering::Equal };
+
+        section.then_with(|| match order {
+            SortOrder::Layout => Ordering::Equal,
+            SortOrder::AppId => app_rank(a.app_id).cmp(&app_rank(b.app_id)),
+            SortOrder::Opened => a.opened.cmp(&b.opened),
+            SortOrder::Recent => b.focused_at.cmp(&a.focused_at),
+            SortOrder::Manual => manual_rank(&a).cmp(&manual_rank(&b)),
+        })
+    });
+}
+
+/// Pinned apps come first in pinned order, each as its windows or as a launcher when it has
+/// none, followed by the remaining windows in their original order.
+pub fn arrange<'a, W>(pinned: &'a [String], windows: Vec<W>, app_id: impl Fn(&W) -> Option<&str>) -> Vec<Slot<'a, W>> {
+    let mut remaining: Vec<Option<W>> = windows.into_iter().map(Some).collect();
+    let mut slots = Vec::with_capacity(remaining.len() + pinned.len());
+
+    for pin in pinned {
+        let before = slots.len();
+        for window in remaining.iter_mut() {
+            if window.as_ref().and_then(&app_id).is_some_and(|id| matches_app(pin, id)) {
+                slots.extend(window.take().map(Slot::Window));
+            }
+        }
+        if slots.len() == before {
+            slots.push(Slot::Launcher(pin.as_str()));
+        }
+    }
+
+    slots.extend(remaining.into_iter().flatten().map(Slot::Window));
+    slots
+}
+
+/// Gathers windows into one run per section key, each run led by a `Section` slot, keeping the
+/// slot order within a section. Launchers stay in front.
+pub fn sections<'a, W: Copy, K: Ord>(slots: Vec<Slot<'a, W>>, key: impl Fn(&W) -> K) -> Vec<Slot<'a, W>> {
+    let (mut windows, mut arranged): (Vec<_>, Vec<_>) = slots.into_iter().partition(|slot| matches!(slot, Slot::Window(_)));
+    windows.sort_by_key(|slot| match slot {
+        Slot::Window(window) => Some(key(window)),
+        _ => None,
+    });
+
+    let mut current = None;
+    for slot in windows {
+        if let Slot::Window(window) = &slot {
+            let section = key(window);
+            if current.as_ref() != Some(&section) {
+                arranged.push(Slot::Section(*window));
+                current = Some(section);
+            }
+        }
+        arranged.push(slot);
+    }
+    arranged
+}
+
+/// Collapses windows sharing an app id into one group at the position of the first of them.
+/// Apps with a single window keep a plain window slot, and groups never span sections.
+pub fn group_by_app<'a, W>(slots: Vec<Slot<'a, W>>, app_id: impl Fn(&W) -> Option<&str>) -> Vec<Slot<'a, W>> {
+    let mut arranged = Vec::with_capacity(slots.len());
+    let mut groups: HashMap<String, usize> = HashMap::new();
+
+    for slot in slots {
+        let window = match slot {
+            Slot::Window(window) => window,
+            Slot::Section(_) => {
+                groups.clear();
+                arranged.push(slot);
+                continue;
+            }
+            slot => {
+                arranged.push(slot);
+                continue;
+            }
+        };
+
+        let key = app_id(&window).map(str::to_string);
+        match key.as_ref().and_then(|key| groups.get(key)) {
+            Some(&index) => {
+                if let Slot::Group(members) = &mut arranged[index] {
+                    members.push(window);
+                }
+            }
+            None => {
+                if let Some(key) = key {
+                    groups.insert(key, arranged.len());
+                }
+                arranged.push(Slot::Group(vec![window]));
+            }
+        }
+    }
+
+    arranged
+        .into_iter()
+        .map(|slot| match slot {
+            Slot::Group(mut members) if members.len() == 1 => Slot::Window(members.remove(0)),
+            slot => slot,
+        })
+        .collect()
+}
+
+/// Wraps runs of adjacent windows with the same column key into a column slot.
+pub fn columns<'a, W, K: PartialEq>(slots: Vec<Slot<'a, W>>, key: impl Fn(&W) -> Option<K>) -> Vec<Slot<'a, W>> {
+    fn flush<'a, W>(arranged: &mut Vec<Slot<'a, W>>, run: &mut Vec<W>) {
+        match run.len() {
+            0 => {}
+            1 => arranged.extend(run.pop().map(Slot::Window)),
+            _ => arranged.push(Slot::Column(std::mem::take(run))),
+        }
+    }
+
+    let mut arranged = Vec::with_capacity(slots.len());
+    let mut run = Vec::new();
+    let mut run_key = None;
+
+    for slot in slots {
+        match slot {
+            Slot::Window(window) => {
+                let column = key(&window);
+                if column.is_none() || column != run_key {
+                    flush(&mut arranged, &mut run);
+                    run_key = column;
+                }
+                run.push(window);
+            }
+            slot => {
+                flush(&mut arranged, &mut run);
+                run_key = None;
+                arranged.push(slot);
+            }
+        }
+    }
+
+    flush(&mut arranged, &mut run);
+    arranged
+}
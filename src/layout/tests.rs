@@ -0,0 +1,107 @@
+use super::*;
+
+fn pins(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+fn layout<'a>(slots: &[Slot<'a, (u64, &'a str)>]) -> Vec<String> {
+    slots
+        .iter()
+        .map(|slot| match slot {
+            Slot::Launcher(pin) => format!("launch {pin}"),
+            Slot::Window((id, _)) => id.to_string(),
+            Slot::Column(members) => format!("column {:?}", members.iter().map(|(id, _)| *id).collect::<Vec<_>>()),
+            Slot::Section((id, _)) => format!("section of {id}"),
+            Slot::Group(members) => format!("{:?}", members.iter().map(|(id, _)| *id).collect::<Vec<_>>()),
+        })
+        .collect()
+}
+
+#[test]
+fn pinned_apps_lead_in_pinned_order() {
+    let pinned = pins(&["foot", "firefox.desktop", "signal"]);
+    let windows = vec![(1, "kitty"), (2, "firefox"), (3, "foot"), (4, "firefox"), (5, "mpv")];
+
+    let slots = arrange(&pinned, windows, |(_, app_id)| Some(*app_id));
+
+    assert_eq!(layout(&slots), ["3", "2", "4", "launch signal", "1", "5"]);
+}
+
+#[test]
+fn groups_collect_windows_at_the_first_members_slot() {
+    let pinned = pins(&["firefox"]);
+    let windows = vec![(1, "foot"), (2, "firefox"), (3, "mpv"), (4, "foot"), (5, "firefox"), (6, "foot")];
+
+    let slots = group_by_app(arrange(&pinned, windows, |(_, app_id)| Some(*app_id)), |(_, app_id)| Some(*app_id));
+
+    assert_eq!(layout(&slots), ["[2, 5]", "[1, 4, 6]", "3"]);
+}
+
+#[test]
+fn windows_without_app_id_are_never_grouped() {
+    let windows = vec![(1, ""), (2, ""), (3, "foot")];
+
+    let slots = group_by_app(arrange(&[], windows, |_| None), |(_, app_id)| Some(*app_id).filter(|id| !id.is_empty()));
+
+    assert_eq!(layout(&slots), ["1", "2", "3"]);
+}
+
+#[test]
+fn sections_split_windows_by_workspace() {
+    let pinned = pins(&["mpv", "signal"]);
+    let workspace = |id: &u64| if *id < 10 { 1 } else { 2 };
+    let windows = vec![(1, "foot"), (2, "foot"), (11, "foot"), (12, "mpv"), (3, "kitty")];
+
+    let slots = sections(arrange(&pinned, windows, |(_, app_id)| Some(*app_id)), |(id, _)| workspace(id));
+    assert_eq!(layout(&slots), ["launch signal", "section of 1", "1", "2", "3", "section of 12", "12", "11"]);
+
+    let slots = group_by_app(slots, |(_, app_id)| Some(*app_id));
+    assert_eq!(layout(&slots), ["launch signal", "section of 1", "[1, 2]", "3", "section of 12", "12", "11"]);
+}
+
+#[test]
+fn adjacent_tiles_of_a_column_share_a_slot() {
+    let column = |id: &u64| (*id >= 10).then_some(id / 10);
+    let windows = vec![(1, "foot"), (11, "foot"), (12, "mpv"), (21, "foot"), (2, "kitty"), (22, "foot")];
+
+    let slots = columns(arrange(&[], windows, |_| None), |(id, _)| column(id));
+
+    assert_eq!(layout(&slots), ["1", "column [11, 12]", "21", "2", "22"]);
+}
+
+struct TestWindow {
+    id: u64,
+    workspace: u8,
+    app_id: &'static str,
+    focused_at: Option<u64>,
+}
+
+fn sorted(order: SortOrder, by_workspace: bool, manual: &[u64]) -> Vec<u64> {
+    let test_window = |id, workspace, app_id, focused_at| TestWindow { id, workspace, app_id, focused_at };
+    let mut windows = vec![
+        test_window(3, 1, "kitty", Some(5)),
+        test_window(1, 1, "Firefox", None),
+        test_window(4, 2, "alacritty", Some(9)),
+        test_window(2, 1, "", Some(7)),
+    ];
+
+    sort(&mut windows, order, by_workspace, manual, |w| SortKeys {
+        id: w.id,
+        workspace: (w.workspace, None),
+        app_id: Some(w.app_id).filter(|id| !id.is_empty()),
+        opened: w.id,
+        focused_at: w.focused_at.map(|secs| (secs, 0)),
+        saved: (w.app_id == "alacritty").then_some(0),
+    });
+    windows.iter().map(|w| w.id).collect()
+}
+
+#[test]
+fn sort_orders_keep_workspaces_first_when_asked() {
+    assert_eq!(sorted(SortOrder::Layout, true, &[]), [3, 1, 4, 2]);
+    assert_eq!(sorted(SortOrder::AppId, true, &[]), [1, 3, 2, 4]);
+    assert_eq!(sorted(SortOrder::AppId, false, &[]), [4, 1, 3, 2]);
+    assert_eq!(sorted(SortOrder::Opened, true, &[]), [1, 2, 3, 4]);
+    assert_eq!(sorted(SortOrder::Recent, false, &[]), [4, 2, 3, 1]);
+    assert_eq!(sorted(SortOrder::Manual, false, &[2, 3]), [4, 2, 3, 1]);
+}
@@ -8,26 +8,35 @@ use settings::Settings;
 use tracing_subscriber::{EnvFilter, fmt::format::FmtSpan};
 use waybar_cffi::{
     Module,
-    gtk::{self, Orientation, ReliefStyle, ScrolledWindow, gio, glib::MainContext, traits::{AdjustmentExt, BoxExt, ButtonExt, ContainerExt, ScrolledWindowExt, StyleContextExt, WidgetExt}},
+    gtk::{self, Orientation, ReliefStyle, ScrolledWindow, gio, glib::MainContext, prelude::{Cast, IsA}, traits::{AdjustmentExt, BoxExt, ButtonExt, ContainerExt, ScrolledWindowExt, StyleContextExt, WidgetExt}},
     waybar_module,
 };
 
+mod actions;
+mod command;
 mod compositor;
 mod errors;
 mod global;
 mod icons;
+mod layout;
 mod notifications;
+mod order;
+mod pinned;
 mod screen;
 mod settings;
+mod state;
 mod system;
+#[cfg(test)]
+mod testing;
 mod widget;
 
-use compositor::{WindowInfo, WindowSnapshot};
+use compositor::{CompositorEvent, WindowInfo, WindowSnapshot};
 use errors::ModuleError;
 use global::{EventMessage, SharedState};
 use notifications::NotificationData;
 use system::ProcessInfo;
-use widget::WindowButton;
+use layout::Slot;
+use widget::{ColumnBox, GroupButton, LauncherButton, SectionLabel, WindowButton};
 
 static LOGGING: LazyLock<()> = LazyLock::new(|| {
     if let Err(e) = tracing_subscriber::fmt()
@@ -228,10 +237,15 @@ fn ease_out_cubic(t: f64) -> f64 {
 
 struct ModuleInstance {
     buttons: BTreeMap<u64, WindowButton>,
+    launchers: BTreeMap<String, LauncherButton>,
+    groups: BTreeMap<String, GroupButton>,
+    sections: BTreeMap<u64, SectionLabel>,
+    columns: BTreeMap<(u64, usize), ColumnBox>,
     container: gtk::Box,
     scrolled_window: ScrolledWindow,
     main_container: gtk::Box,
     previous_snapshot: Option<WindowSnapshot>,
+    previous_order: Vec<u64>,
     current_output: Option<String>,
     state: SharedState,
 }
@@ -240,10 +254,15 @@ impl ModuleInstance {
     fn create(state: SharedState, container: gtk::Box, scrolled_window: ScrolledWindow, main_container: gtk::Box) -> Self {
         Self {
             buttons: BTreeMap::new(),
+            launchers: BTreeMap::new(),
+            groups: BTreeMap::new(),
+            sections: BTreeMap::new(),
+            columns: BTreeMap::new(),
             container,
             scrolled_window,
             main_container,
             previous_snapshot: None,
+            previous_order: Vec::new(),
             current_output: None,
             state,
         }
@@ -252,21 +271,19 @@ impl ModuleInstance {
     async fn run_event_loop(&mut self) {
         let display_filter = Arc::new(Mutex::new(self.determine_display_filter().await));
 
-        let mut event_stream = match self.state.create_event_stream() {
-            Ok(stream) => Box::pin(stream),
-            Err(e) => {
-                tracing::error!(%e, "failed to create event stream");
-                return;
-            }
-        };
+        let mut event_stream = Box::pin(self.state.create_event_stream());
 
         while let Some(event) = event_stream.next().await {
             match event {
                 EventMessage::Notification(notif) => self.handle_notification(notif).await,
-                EventMessage::WindowUpdate(snapshot) => {
+                EventMessage::Compositor(CompositorEvent::Connection { connected }) => {
+                    self.handle_connection_change(connected)
+                }
+                EventMessage::Compositor(CompositorEvent::Windows(snapshot)) => {
                     self.handle_window_update(snapshot, display_filter.clone()).await
                 }
-                EventMessage::Workspaces(_) => {
+                EventMessage::Compositor(CompositorEvent::Workspaces(workspaces)) => {
+                    tracing::trace!(count = workspaces.len(), "workspaces updated");
                     let updated_filter = self.determine_display_filter().await;
                     let filter_changed = {
                         let mut filter_lock = display_filter.lock().expect("display filter lock");
@@ -282,12 +299,44 @@ impl ModuleInstance {
                         }
                     }
                 }
+                EventMessage::Compositor(CompositorEvent::Outputs(outputs)) => {
+                    tracing::debug!(?outputs, "compositor outputs changed");
+                }
+                EventMessage::PinsChanged => {
+                    if let Some(snapshot) = self.previous_snapshot.clone() {
+                        self.handle_window_update(snapshot, display_filter.clone()).await;
+                    }
+                }
+                EventMessage::BarReordered => {
+                    self.remember_bar_order();
+                    if let Some(snapshot) = self.previous_snapshot.clone() {
+                        self.handle_window_update(snapshot, display_filter.clone()).await;
+                    }
+                }
+                EventMessage::Compositor(CompositorEvent::Overview { is_open }) => {
+                    let style_ctx = self.container.style_context();
+                    if is_open {
+                        style_ctx.add_class("overview");
+                    } else {
+                        style_ctx.remove_class("overview");
+                    }
+                }
             }
         }
     }
 
+    fn handle_connection_change(&self, connected: bool) {
+        let style_ctx = self.container.style_context();
+        if connected {
+            style_ctx.remove_class("disconnected");
+        } else {
+            tracing::warn!("lost connection to compositor");
+            style_ctx.add_class("disconnected");
+        }
+    }
+
     async fn update_output_and_resize(&mut self) -> bool {
-        let new_output = self.get_current_output_name();
+        let new_output = self.get_current_output_name().await;
 
         if self.current_output.as_deref() != new_output.as_deref() {
             self.current_output = new_output.clone();
@@ -301,14 +350,24 @@ impl ModuleInstance {
         false
     }
 
-    fn get_current_output_name(&self) -> Option<String> {
+    async fn get_current_output_name(&self) -> Option<String> {
+        let compositor = self.state.compositor().clone();
+        let outputs = match gio::spawn_blocking(move || compositor.query_outputs()).await {
+            Ok(Ok(outputs)) => outputs,
+            Ok(Err(e)) => {
+                tracing::warn!(%e, "failed to query compositor outputs");
+                return None;
+            }
+            Err(_) => {
+                tracing::error!("task spawning error");
+                return None;
+            }
+        };
+
         let gdk_window = self.container.window()?;
         let display = gdk_window.display();
         let monitor = display.monitor_at_window(&gdk_window)?;
-        
-        let compositor = self.state.compositor().clone();
-        let outputs = compositor.query_outputs().ok()?;
-        
+
         for (output_name, output_info) in outputs.into_iter() {
             let match_result = screen::OutputMatcher::compare(&monitor, &output_info);
             if match_result == screen::OutputMatcher::all() {
@@ -379,13 +438,10 @@ impl ModuleInstance {
 
             loop {
                 if let Some(window) = process_map.lookup(process_id) {
-                    if !window.is_focused {
-                        if let Some(button) = self.buttons.get(&window.id) {
-                            tracing::trace!(?button, ?window, process_id, 
-                                "marking window as urgent via PID match");
-                            button.mark_urgent();
-                            matched = true;
-                        }
+                    if !window.is_focused && self.mark_window_urgent(window.id) {
+                        tracing::trace!(?window, process_id, 
+                            "marking window as urgent via PID match");
+                        matched = true;
                     }
                 }
 
@@ -437,10 +493,9 @@ impl ModuleInstance {
             };
 
             if app_identifier == mapped_entry {
-                if let Some(button) = self.buttons.get(&window.id) {
-                    tracing::trace!(app_identifier, ?button, ?window, 
+                if self.mark_window_urgent(window.id) {
+                    tracing::trace!(app_identifier, ?window, 
                         "exact app ID match for notification");
-                    button.mark_urgent();
                     exact_match = true;
                 }
             } else if fuzzy_enabled {
@@ -462,13 +517,22 @@ impl ModuleInstance {
 
         if !exact_match {
             for window_id in fuzzy_matches {
-                if let Some(button) = self.buttons.get(&window_id) {
-                    button.mark_urgent();
-                }
+                self.mark_window_urgent(window_id);
             }
         }
     }
 
+    fn mark_window_urgent(&self, window_id: u64) -> bool {
+        if let Some(button) = self.buttons.get(&window_id) {
+            button.mark_urgent();
+        } else if let Some(group) = self.groups.values().find(|group| group.contains(window_id)) {
+            group.mark_urgent();
+        } else {
+            return false;
+        }
+        true
+    }
+
     #[tracing::instrument(level = "DEBUG", skip(self))]
     async fn handle_window_update(
         &mut self,
@@ -478,10 +542,11 @@ impl ModuleInstance {
         self.update_output_and_resize().await;
 
         let mut removed_windows = self.buttons.keys().copied().collect::<BTreeSet<_>>();
-        let config = self.state.settings();
+        let state = self.state.clone();
+        let config = state.settings();
         let mut new_button_added = false;
 
-        for window in snapshot.iter().filter(|w| {
+        let mut visible_windows: Vec<&WindowInfo> = snapshot.iter().filter(|w| {
             if !filter.lock().expect("filter lock").should_display(w.get_output().unwrap_or_default()) {
                 return false;
             }
@@ -491,64 +556,142 @@ impl ModuleInstance {
                 }
             }
             true
-        }) {
-            let button_count = (self.buttons.len() + 1) as i32;
-            let output = self.current_output.as_deref();
-            let min_width = self.state.settings().min_button_width(output);
-            let max_width = self.state.settings().max_button_width(output);
-            let total_limit = self.state.settings().max_taskbar_width_for_output(output);
-            
-            let initial_width = if max_width * button_count > total_limit {
-                (total_limit / button_count).max(min_width).max(1)
-            } else {
-                max_width
-            }.max(1);
-
-            let button = self.buttons.entry(window.id).or_insert_with(|| {
-                new_button_added = true;
-                let btn = WindowButton::create(&self.state, window);
-                btn.get_widget().set_size_request(initial_width, -1);
-                self.container.add(btn.get_widget());
-                btn
-            });
+        }).collect();
+        layout::sort(&mut visible_windows, config.sort_by(), config.sort_by_workspace(), &self.previous_order, |w| {
+            layout::SortKeys {
+                id: w.id,
+                workspace: (w.workspace().idx, w.get_output()),
+                app_id: w.app_id.as_deref(),
+                opened: w.opened(),
+                focused_at: w.focus_timestamp.map(|t| (t.secs, t.nanos)),
+                saved: state.bar_order().rank(w.app_id.as_deref(), w.title.as_deref()),
+            }
+        });
+        self.previous_order = visible_windows.iter().map(|w| w.id).collect();
 
-            button.update_focus(window.is_focused);
-            button.update_title(window.title.as_deref());
-            
-            if window.is_focused {
-                let button_widget = button.get_widget().clone();
-                let scrolled = self.scrolled_window.clone();
-                gtk::glib::idle_add_local_once(move || {
-                    let allocation = button_widget.allocation();
-                    let hadj = scrolled.hadjustment();
-                    let button_x = allocation.x() as f64;
-                    let button_width = allocation.width() as f64;
-                    let current_scroll = hadj.value();
-                    let page_size = hadj.page_size();
-                    
-                    let button_right = button_x + button_width;
-                    let visible_right = current_scroll + page_size;
-                    
-                    if button_x < current_scroll {
-                       hadj.set_value(button_x);
-                    } else if button_right > visible_right {
-                       hadj.set_value(button_right - page_size);
+        let pinned_apps = self.state.pinned().list();
+        let mut slots = layout::arrange(&pinned_apps, visible_windows, |w| w.app_id.as_deref());
+        if config.workspace_sections() {
+            slots = layout::sections(slots, |w| (w.get_output(), w.workspace().idx, w.workspace().id));
+        }
+        if config.group_by_app() {
+            slots = layout::group_by_app(slots, |w| w.app_id.as_deref());
+        }
+        slots = layout::columns(slots, |w| column_key(w));
+        let mut removed_launchers = self.launchers.keys().cloned().collect::<BTreeSet<_>>();
+        let mut removed_groups = self.groups.keys().cloned().collect::<BTreeSet<_>>();
+        let mut removed_sections = self.sections.keys().copied().collect::<BTreeSet<_>>();
+        let mut removed_columns = self.columns.keys().copied().collect::<BTreeSet<_>>();
+        let mut placed_columns = BTreeSet::new();
+
+        for slot in slots {
+            let window = match slot {
+                Slot::Window(window) => window,
+                Slot::Column(members) => {
+                    // A manual order can split a column; only its first run gets the container.
+                    let Some(key) = column_key(members[0]).filter(|key| placed_columns.insert(*key)) else {
+                        let container = self.container.clone();
+                        for window in members {
+                            new_button_added |= self.place_window(window, &container, None);
+                            removed_windows.remove(&window.id);
+                        }
+                        continue;
+                    };
+                    removed_columns.remove(&key);
+                    let column = self.columns.entry(key).or_insert_with(ColumnBox::create);
+                    column.update(members[0].column().is_some_and(|column| column.tabbed));
+                    let column = column.get_widget().clone();
+                    attach(&self.container, &column);
+
+                    let last = members.len() - 1;
+                    for (index, window) in members.into_iter().enumerate() {
+                        new_button_added |= self.place_window(window, &column, Some((index == 0, index == last)));
+                        removed_windows.remove(&window.id);
                     }
-                });
-            }
+                    continue;
+                }
+                Slot::Section(first) => {
+                    let workspace = first.workspace();
+                    removed_sections.remove(&workspace.id);
+                    let section = self.sections.entry(workspace.id).or_insert_with(|| {
+                        let section = SectionLabel::create(&self.state, workspace.id);
+                        self.container.add(section.get_widget());
+                        section
+                    });
+
+                    let output = first.get_output().filter(|_| config.show_all_outputs());
+                    section.update(workspace, output);
+                    self.container.reorder_child(section.get_widget(), -1);
+                    continue;
+                }
+                Slot::Group(members) => {
+                    let Some(app_id) = members[0].app_id.clone() else {
+                        continue;
+                    };
+                    removed_groups.remove(&app_id);
+                    let group = self.groups.entry(app_id).or_insert_with_key(|app_id| {
+                        new_button_added = true;
+                        let group = GroupButton::create(&self.state, app_id);
+                        self.container.add(group.get_widget());
+                        group
+                    });
+
+                    group.update(members.into_iter().cloned().collect());
+                    if group.is_focused() {
+                        scroll_into_view(&self.scrolled_window, group.get_widget());
+                    }
+                    self.container.reorder_child(group.get_widget(), -1);
+                    continue;
+                }
+                Slot::Launcher(pin) => {
+                    removed_launchers.remove(pin);
+                    let launcher = self.launchers.entry(pin.to_string()).or_insert_with(|| {
+                        let launcher = LauncherButton::create(&self.state, pin);
+                        self.container.add(launcher.get_widget());
+                        launcher
+                    });
+                    self.container.reorder_child(launcher.get_widget(), -1);
+                    continue;
+                }
+            };
 
+            let container = self.container.clone();
+            new_button_added |= self.place_window(window, &container, None);
             removed_windows.remove(&window.id);
-            self.container.reorder_child(button.get_widget(), -1);
         }
 
         for window_id in removed_windows {
             if let Some(button) = self.buttons.remove(&window_id) {
-                self.container.remove(button.get_widget());
+                detach(button.get_widget());
+            }
+        }
+
+        for key in removed_columns {
+            if let Some(column) = self.columns.remove(&key) {
+                self.container.remove(column.get_widget());
+            }
+        }
+
+        for app_id in removed_groups {
+            if let Some(group) = self.groups.remove(&app_id) {
+                self.container.remove(group.get_widget());
             }
         }
 
-        if !self.buttons.is_empty() {
-            let button_count = self.buttons.len() as i32;
+        for workspace_id in removed_sections {
+            if let Some(section) = self.sections.remove(&workspace_id) {
+                self.container.remove(section.get_widget());
+            }
+        }
+
+        for pin in removed_launchers {
+            if let Some(launcher) = self.launchers.remove(&pin) {
+                self.container.remove(launcher.get_widget());
+            }
+        }
+
+        if !self.buttons.is_empty() || !self.groups.is_empty() {
+            let button_count = (self.buttons.len() + self.groups.len()) as i32;
             let output = self.current_output.as_deref();
             let min_width = self.state.settings().min_button_width(output);
             let max_width = self.state.settings().max_button_width(output);
@@ -564,6 +707,10 @@ impl ModuleInstance {
                 button.get_widget().set_size_request(final_width, -1);
                 button.resize_for_width(final_width);
             }
+            for group in self.groups.values() {
+                group.get_widget().set_size_request(final_width, -1);
+                group.resize_for_width(final_width);
+            }
         }
 
         self.container.show_all();
@@ -578,6 +725,115 @@ impl ModuleInstance {
 
         self.previous_snapshot = Some(snapshot);
     }
+
+    /// Saves the order buttons were dragged into.
+    fn remember_bar_order(&mut self) {
+        let Some(snapshot) = &self.previous_snapshot else {
+            return;
+        };
+
+        let mut widgets = Vec::new();
+        for child in self.container.children() {
+            match child.downcast::<gtk::Box>() {
+                Ok(column) => widgets.extend(column.children()),
+                Err(child) => widgets.push(child),
+            }
+        }
+
+        let windows: Vec<&WindowInfo> = widgets
+            .iter()
+            .filter_map(|widget| self.buttons.iter().find(|(_, button)| button.get_widget().upcast_ref::<gtk::Widget>() == widget))
+            .filter_map(|(id, _)| snapshot.iter().find(|w| w.id == *id))
+            .collect();
+        self.previous_order = windows.iter().map(|w| w.id).collect();
+
+        let keys: Vec<_> = windows.iter().map(|w| (w.app_id.as_deref(), w.title.as_deref())).collect();
+        if let Err(e) = self.state.bar_order().remember(&keys) {
+            tracing::warn!(%e, "failed to save taskbar order");
+        }
+    }
+
+    /// Creates or updates the window's button and moves it to the end of `parent`. Returns
+    /// whether a new button was created.
+    fn place_window(&mut self, window: &WindowInfo, parent: &gtk::Box, tile: Option<(bool, bool)>) -> bool {
+        let button_count = (self.buttons.len() + self.groups.len() + 1) as i32;
+        let output = self.current_output.as_deref();
+        let min_width = self.state.settings().min_button_width(output);
+        let max_width = self.state.settings().max_button_width(output);
+        let total_limit = self.state.settings().max_taskbar_width_for_output(output);
+
+        let initial_width = if max_width * button_count > total_limit {
+            (total_limit / button_count).max(min_width).max(1)
+        } else {
+            max_width
+        }.max(1);
+
+        let mut created = false;
+        let button = self.buttons.entry(window.id).or_insert_with(|| {
+            created = true;
+            let btn = WindowButton::create(&self.state, window);
+            btn.get_widget().set_size_request(initial_width, -1);
+            btn
+        });
+
+        button.update_window(window);
+        button.update_focus(window.is_focused);
+        button.update_title(window.title.as_deref());
+        button.update_tile(tile);
+
+        if window.is_focused {
+            scroll_into_view(&self.scrolled_window, button.get_widget());
+        }
+
+        attach(parent, button.get_widget());
+        created
+    }
+}
+
+fn column_key(window: &WindowInfo) -> Option<(u64, usize)> {
+    window.column().filter(|column| column.tiles > 1)?;
+    let (index, _) = window.layout.pos_in_scrolling_layout?;
+    Some((window.workspace().id, index))
+}
+
+/// Moves the widget to the end of `parent`, taking it out of any other container first.
+fn attach(parent: &gtk::Box, widget: &impl IsA<gtk::Widget>) {
+    let current = widget.parent();
+    if current.as_ref() != Some(parent.upcast_ref()) {
+        if let Some(previous) = current.and_then(|previous| previous.downcast::<gtk::Container>().ok()) {
+            previous.remove(widget);
+        }
+        parent.add(widget);
+    }
+    parent.reorder_child(widget, -1);
+}
+
+fn detach(widget: &impl IsA<gtk::Widget>) {
+    if let Some(parent) = widget.parent().and_then(|parent| parent.downcast::<gtk::Container>().ok()) {
+        parent.remove(widget);
+    }
+}
+
+fn scroll_into_view(scrolled: &ScrolledWindow, widget: &gtk::Button) {
+    let button_widget = widget.clone();
+    let scrolled = scrolled.clone();
+    gtk::glib::idle_add_local_once(move || {
+        let allocation = button_widget.allocation();
+        let hadj = scrolled.hadjustment();
+        let button_x = allocation.x() as f64;
+        let button_width = allocation.width() as f64;
+        let current_scroll = hadj.value();
+        let page_size = hadj.page_size();
+        
+        let button_right = button_x + button_width;
+        let visible_right = current_scroll + page_size;
+        
+        if button_x < current_scroll {
+           hadj.set_value(button_x);
+        } else if button_right > visible_right {
+           hadj.set_value(button_right - page_size);
+        }
+    });
 }
 
 struct ProcessWindowMap<'a>(HashMap<i64, &'a WindowInfo>);
@@ -0,0 +1,141 @@
+use std::{path::PathBuf, sync::Mutex};
+use async_channel::{Receiver, Sender};
+use serde::{Deserialize, Serialize};
+use crate::{errors::ModuleError, state};
+
+#[cfg(test)]
+mod tests;
+
+/// Remembers a window by app id, and by title too when the app had several windows.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderEntry {
+    pub app_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// Most entries kept in the saved order; title entries go stale whenever a title changes.
+const MAX_ENTRIES: usize = 64;
+
+/// The taskbar order arranged by dragging buttons with `sort_by: "manual"`.
+#[derive(Debug)]
+pub struct BarOrder {
+    path: Option<PathBuf>,
+    entries: Mutex<Vec<OrderEntry>>,
+    drops: (Sender<()>, Receiver<()>),
+}
+
+impl BarOrder {
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let entries = match path.as_deref().map(state::read) {
+            Some(Ok(Some(entries))) => entries,
+            Some(Err(e)) => {
+                tracing::warn!(%e, "ignoring saved taskbar order");
+                Vec::new()
+            }
+            _ => Vec::new(),
+        };
+
+        Self {
+            path,
+            entries: Mutex::new(entries),
+            drops: async_channel::unbounded(),
+        }
+    }
+
+    /// Position of the entry for a window: an exact title match wins over the app's plain
+    /// entry, which wins over the app's entries for other titles.
+    pub fn rank(&self, app_id: Option<&str>, title: Option<&str>) -> Option<usize> {
+        let app_id = app_id?;
+        let entries = self.entries.lock().expect("bar order lock");
+        let position = |matches: &dyn Fn(&OrderEntry) -> bool| {
+            entries.iter().position(|entry| entry.app_id == app_id && matches(entry))
+        };
+
+        position(&|entry| title.is_some() && entry.title.as_deref() == title)
+            .or_else(|| position(&|entry| entry.title.is_none()))
+            .or_else(|| position(&|_| true))
+    }
+
+    /// Saves the order of the windows now on the bar, given as `(app_id, title)` pairs. Entries
+    /// for windows that aren't on the bar keep their place after their previous neighbour.
+    pub fn remember(&self, windows: &[(Option<&str>, Option<&str>)]) -> Result<(), ModuleError> {
+        let mut remembered: Vec<OrderEntry> = Vec::new();
+        for (app_id, title) in windows {
+            let Some(app_id) = app_id else {
+                continue;
+            };
+            let shared = windows.iter().filter(|(other, _)| other == &Some(*app_id)).count() > 1;
+            let entry = OrderEntry {
+                app_id: app_id.to_string(),
+                title: title.filter(|_| shared).map(str::to_string),
+            };
+            if !remembered.contains(&entry) {
+                remembered.push(entry);
+            }
+        }
+
+        let present = remembered.clone();
+        let entries = {
+            let mut entries = self.entries.lock().expect("bar order lock");
+            // Other bars save to the same file, so merge into what is on disk now.
+            if let Some(saved) = self.saved() {
+                *entries = saved;
+            }
+            let mut previous: Option<usize> = None;
+            for entry in entries.drain(..) {
+                match remembered.iter().position(|known| known == &entry) {
+                    Some(index) => previous = Some(index),
+                    None => {
+                        let index = previous.map_or(0, |index| index + 1);
+                        remembered.insert(index, entry);
+                        previous = Some(index);
+                    }
+                }
+            }
+            prune_absent(&mut remembered, &present);
+            *entries = remembered;
+            entries.clone()
+        };
+
+        match &self.path {
+            Some(path) => state::write(path, &entries),
+            None => Ok(()),
+        }
+    }
+
+    fn saved(&self) -> Option<Vec<OrderEntry>> {
+        match state::read(self.path.as_deref()?) {
+            Ok(saved) => saved,
+            Err(e) => {
+                tracing::warn!(%e, "ignoring saved taskbar order");
+                None
+            }
+        }
+    }
+
+    /// Signals that a button was dropped at a new place on the bar.
+    pub fn dropped(&self) {
+        let _ = self.drops.0.try_send(());
+    }
+
+    pub fn drops(&self) -> Receiver<()> {
+        self.drops.1.clone()
+    }
+}
+
+/// Drops entries for windows that aren't on the bar, titled ones first and from the end of the
+/// order, until at most `MAX_ENTRIES` remain.
+fn prune_absent(entries: &mut Vec<OrderEntry>, present: &[OrderEntry]) {
+    let mut excess = entries.len().saturating_sub(MAX_ENTRIES);
+    for titled in [true, false] {
+        let mut index = entries.len();
+        while excess > 0 && index > 0 {
+            index -= 1;
+            if entries[index].title.is_some() == titled && !present.contains(&entries[index]) {
+                entries.remove(index);
+                excess -= 1;
+            }
+        }
+    }
+}
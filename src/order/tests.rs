@@ -0,0 +1,63 @@
+use super::*;
+use crate::testing::scratch_dir;
+
+#[test]
+fn titles_only_tell_apart_windows_of_the_same_app() {
+    let order = BarOrder::load(None);
+
+    order
+        .remember(&[(Some("thunderbird"), Some("Inbox")), (Some("foot"), Some("~")), (Some("foot"), Some("vim")), (None, Some("?"))])
+        .unwrap();
+
+    assert_eq!(order.rank(Some("thunderbird"), Some("Drafts")), Some(0));
+    assert_eq!(order.rank(Some("foot"), Some("vim")), Some(2));
+    assert_eq!(order.rank(Some("foot"), Some("htop")), Some(1));
+    assert_eq!(order.rank(Some("kitty"), None), None);
+}
+
+#[test]
+fn absent_windows_keep_their_place() {
+    let path = scratch_dir().join("order.json");
+    let order = BarOrder::load(Some(path.clone()));
+    order.remember(&[(Some("mail"), None), (Some("chat"), None), (Some("editor"), None)]).unwrap();
+
+    order.remember(&[(Some("editor"), None), (Some("chat"), None)]).unwrap();
+
+    let reloaded = BarOrder::load(Some(path));
+    let ranks: Vec<_> = ["mail", "chat", "editor"].iter().map(|app| reloaded.rank(Some(app), None)).collect();
+    assert_eq!(ranks, [Some(0), Some(2), Some(1)]);
+}
+
+#[test]
+fn bars_keep_each_others_entries() {
+    let path = scratch_dir().join("order.json");
+    let left = BarOrder::load(Some(path.clone()));
+    let right = BarOrder::load(Some(path.clone()));
+
+    left.remember(&[(Some("mail"), None), (Some("chat"), None)]).unwrap();
+    right.remember(&[(Some("editor"), None), (Some("music"), None)]).unwrap();
+
+    let reloaded = BarOrder::load(Some(path));
+    for app in ["mail", "chat", "editor", "music"] {
+        assert!(reloaded.rank(Some(app), None).is_some(), "{app} was forgotten");
+    }
+    assert_eq!(right.rank(Some("mail"), None), reloaded.rank(Some("mail"), None));
+}
+
+#[test]
+fn stale_titles_are_pruned() {
+    let path = scratch_dir().join("order.json");
+    let order = BarOrder::load(Some(path.clone()));
+    order.remember(&[(Some("mail"), None)]).unwrap();
+
+    for n in 0..MAX_ENTRIES {
+        let title = format!("page {n}");
+        order.remember(&[(Some("browser"), Some(title.as_str())), (Some("browser"), Some("home")), (Some("mail"), None)]).unwrap();
+    }
+
+    let saved: Vec<OrderEntry> = state::read(&path).unwrap().unwrap();
+    assert_eq!(saved.len(), MAX_ENTRIES);
+    assert!(saved.iter().all(|entry| entry.title.is_some() || entry.app_id == "mail"));
+    assert!(saved.contains(&OrderEntry { app_id: "browser".to_string(), title: Some(format!("page {}", MAX_ENTRIES - 1)) }));
+    assert!(saved.contains(&OrderEntry { app_id: "browser".to_string(), title: Some("home".to_string()) }));
+}
@@ -0,0 +1,77 @@
+use std::{path::PathBuf, sync::Mutex};
+use async_channel::{Receiver, Sender};
+use crate::{errors::ModuleError, state};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug)]
+pub struct PinnedApps {
+    path: Option<PathBuf>,
+    apps: Mutex<Vec<String>>,
+    changes: (Sender<()>, Receiver<()>),
+}
+
+impl PinnedApps {
+    /// Pins from the state file, falling back to the configured list until the user first
+    /// pins or unpins something.
+    pub fn load(configured: &[String], path: Option<PathBuf>) -> Self {
+        let apps = match path.as_deref().map(state::read) {
+            Some(Ok(Some(apps))) => apps,
+            Some(Err(e)) => {
+                tracing::warn!(%e, "ignoring pinned apps state");
+                configured.to_vec()
+            }
+            _ => configured.to_vec(),
+        };
+
+        Self {
+            path,
+            apps: Mutex::new(apps),
+            changes: async_channel::unbounded(),
+        }
+    }
+
+    pub fn list(&self) -> Vec<String> {
+        self.apps.lock().expect("pinned apps lock").clone()
+    }
+
+    pub fn is_pinned(&self, app_id: &str) -> bool {
+        self.apps.lock().expect("pinned apps lock").iter().any(|pin| matches_app(pin, app_id))
+    }
+
+    pub fn pin(&self, app_id: &str) -> Result<(), ModuleError> {
+        self.update(|apps| {
+            if !apps.iter().any(|pin| matches_app(pin, app_id)) {
+                apps.push(app_id.to_string());
+            }
+        })
+    }
+
+    pub fn unpin(&self, app_id: &str) -> Result<(), ModuleError> {
+        self.update(|apps| apps.retain(|pin| !matches_app(pin, app_id)))
+    }
+
+    pub fn changes(&self) -> Receiver<()> {
+        self.changes.1.clone()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Vec<String>)) -> Result<(), ModuleError> {
+        let apps = {
+            let mut apps = self.apps.lock().expect("pinned apps lock");
+            change(&mut apps);
+            apps.clone()
+        };
+        let _ = self.changes.0.try_send(());
+
+        match &self.path {
+            Some(path) => state::write(path, &apps),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Pins may name a desktop entry (`firefox.desktop`) or an app id (`firefox`).
+pub fn matches_app(pin: &str, app_id: &str) -> bool {
+    pin.strip_suffix(".desktop").unwrap_or(pin).eq_ignore_ascii_case(app_id)
+}
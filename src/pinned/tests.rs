@@ -0,0 +1,42 @@
+use super::*;
+use crate::testing::scratch_dir;
+
+fn pins(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn desktop_ids_match_app_ids() {
+    assert!(matches_app("org.gnome.Nautilus.desktop", "org.gnome.Nautilus"));
+    assert!(matches_app("Firefox", "firefox"));
+    assert!(!matches_app("fire", "firefox"));
+}
+
+#[test]
+fn pins_persist_to_the_state_file() {
+    let path = scratch_dir().join("state/pinned.json");
+    let configured = pins(&["foot"]);
+
+    let apps = PinnedApps::load(&configured, Some(path.clone()));
+    assert_eq!(apps.list(), ["foot"]);
+
+    apps.pin("firefox").unwrap();
+    apps.pin("Firefox").unwrap();
+    apps.unpin("foot").unwrap();
+    assert!(apps.changes().try_recv().is_ok());
+
+    let reloaded = PinnedApps::load(&configured, Some(path));
+    assert_eq!(reloaded.list(), ["firefox"]);
+    assert!(reloaded.is_pinned("firefox"));
+    assert!(!reloaded.is_pinned("foot"));
+}
+
+#[test]
+fn unreadable_state_falls_back_to_configured_pins() {
+    let path = scratch_dir().join("pinned.json");
+    std::fs::write(&path, "{not json").unwrap();
+
+    let apps = PinnedApps::load(&pins(&["foot"]), Some(path));
+
+    assert_eq!(apps.list(), ["foot"]);
+}
@@ -1,7 +1,11 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::{Path, PathBuf}, time::Duration};
 use itertools::Itertools;
 use regex::Regex;
 use serde::{Deserialize, Deserializer};
+use crate::command::CommandTemplate;
+
+#[cfg(test)]
+mod tests;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct OutputDimensions {
@@ -49,6 +53,24 @@ pub struct Settings {
     ignore_rules: Vec<IgnoreRule>,
     #[serde(default = "default_context_menu")]
     context_menu: Vec<ContextMenuItem>,
+    #[serde(default)]
+    context_menu_desktop_actions: bool,
+    #[serde(default)]
+    pinned: Vec<String>,
+    #[serde(default)]
+    group_by_app: bool,
+    #[serde(default)]
+    group_click: GroupClick,
+    #[serde(default)]
+    workspace_sections: bool,
+    #[serde(default)]
+    sort_by: SortOrder,
+    #[serde(default = "default_true")]
+    sort_by_workspace: bool,
+    #[serde(default)]
+    debug_record_events: Option<PathBuf>,
+    #[serde(default)]
+    debug_replay_events: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -87,15 +109,190 @@ pub struct AppRule {
 #[derive(Debug, Clone, Deserialize)]
 pub struct ClickActions {
     #[serde(default = "default_left_unfocused")]
-    pub left_click_unfocused: WindowAction,
+    pub left_click_unfocused: ActionSequence,
     #[serde(default = "default_left_focused")]
-    pub left_click_focused: WindowAction,
+    pub left_click_focused: ActionSequence,
     #[serde(default = "default_double_click")]
-    pub double_click: WindowAction,
+    pub double_click: ActionSequence,
     #[serde(default = "default_right_click")]
-    pub right_click: WindowAction,
+    pub right_click: ActionSequence,
     #[serde(default = "default_middle_click")]
-    pub middle_click: WindowAction,
+    pub middle_click: ActionSequence,
+    #[serde(default)]
+    pub double_click_focused: Option<ActionSequence>,
+    #[serde(default)]
+    pub double_click_unfocused: Option<ActionSequence>,
+    #[serde(default)]
+    pub right_click_focused: Option<ActionSequence>,
+    #[serde(default)]
+    pub right_click_unfocused: Option<ActionSequence>,
+    #[serde(default)]
+    pub middle_click_focused: Option<ActionSequence>,
+    #[serde(default)]
+    pub middle_click_unfocused: Option<ActionSequence>,
+    #[serde(default = "default_none")]
+    pub scroll_up: ActionSequence,
+    #[serde(default = "default_none")]
+    pub scroll_down: ActionSequence,
+    #[serde(default = "default_none")]
+    pub scroll_left: ActionSequence,
+    #[serde(default = "default_none")]
+    pub scroll_right: ActionSequence,
+    #[serde(default = "default_none")]
+    pub long_press: ActionSequence,
+    #[serde(default = "default_none")]
+    pub swipe_up: ActionSequence,
+    #[serde(default = "default_none")]
+    pub swipe_down: ActionSequence,
+    #[serde(default = "default_none")]
+    pub swipe_left: ActionSequence,
+    #[serde(default = "default_none")]
+    pub swipe_right: ActionSequence,
+    #[serde(default = "default_none")]
+    pub hover: ActionSequence,
+    #[serde(default = "default_none")]
+    pub drag_hover: ActionSequence,
+    #[serde(default = "default_hover_delay")]
+    hover_delay_ms: u64,
+    #[serde(flatten)]
+    modified: ModifiedBindings,
+}
+
+bitflags::bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Modifiers: u8 {
+        const SHIFT = 1 << 0;
+        const CTRL = 1 << 1;
+        const ALT = 1 << 2;
+        const SUPER = 1 << 3;
+    }
+}
+
+impl Modifiers {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "shift" => Some(Self::SHIFT),
+            "ctrl" | "control" => Some(Self::CTRL),
+            "alt" => Some(Self::ALT),
+            "super" | "logo" | "mod4" => Some(Self::SUPER),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClickBinding {
+    LeftClick,
+    LeftClickUnfocused,
+    LeftClickFocused,
+    DoubleClick,
+    DoubleClickUnfocused,
+    DoubleClickFocused,
+    RightClick,
+    RightClickUnfocused,
+    RightClickFocused,
+    MiddleClick,
+    MiddleClickUnfocused,
+    MiddleClickFocused,
+    ScrollUp,
+    ScrollDown,
+    ScrollLeft,
+    ScrollRight,
+    LongPress,
+    SwipeUp,
+    SwipeDown,
+    SwipeLeft,
+    SwipeRight,
+    Hover,
+    DragHover,
+}
+
+impl ClickBinding {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "left_click" => Self::LeftClick,
+            "left_click_unfocused" => Self::LeftClickUnfocused,
+            "left_click_focused" => Self::LeftClickFocused,
+            "double_click" => Self::DoubleClick,
+            "double_click_unfocused" => Self::DoubleClickUnfocused,
+            "double_click_focused" => Self::DoubleClickFocused,
+            "right_click" => Self::RightClick,
+            "right_click_unfocused" => Self::RightClickUnfocused,
+            "right_click_focused" => Self::RightClickFocused,
+            "middle_click" => Self::MiddleClick,
+            "middle_click_unfocused" => Self::MiddleClickUnfocused,
+            "middle_click_focused" => Self::MiddleClickFocused,
+            "scroll_up" => Self::ScrollUp,
+            "scroll_down" => Self::ScrollDown,
+            "scroll_left" => Self::ScrollLeft,
+            "scroll_right" => Self::ScrollRight,
+            "long_press" => Self::LongPress,
+            "swipe_up" => Self::SwipeUp,
+            "swipe_down" => Self::SwipeDown,
+            "swipe_left" => Self::SwipeLeft,
+            "swipe_right" => Self::SwipeRight,
+            "hover" => Self::Hover,
+            "drag_hover" => Self::DragHover,
+            _ => return None,
+        })
+    }
+
+    pub fn with_focus(self, focused: bool) -> Self {
+        match (self, focused) {
+            (Self::LeftClick, false) => Self::LeftClickUnfocused,
+            (Self::LeftClick, true) => Self::LeftClickFocused,
+            (Self::DoubleClick, false) => Self::DoubleClickUnfocused,
+            (Self::DoubleClick, true) => Self::DoubleClickFocused,
+            (Self::RightClick, false) => Self::RightClickUnfocused,
+            (Self::RightClick, true) => Self::RightClickFocused,
+            (Self::MiddleClick, false) => Self::MiddleClickUnfocused,
+            (Self::MiddleClick, true) => Self::MiddleClickFocused,
+            (binding, _) => binding,
+        }
+    }
+
+    fn fallback(self) -> Option<Self> {
+        match self {
+            Self::LeftClickUnfocused | Self::LeftClickFocused => Some(Self::LeftClick),
+            Self::DoubleClickUnfocused | Self::DoubleClickFocused => Some(Self::DoubleClick),
+            Self::RightClickUnfocused | Self::RightClickFocused => Some(Self::RightClick),
+            Self::MiddleClickUnfocused | Self::MiddleClickFocused => Some(Self::MiddleClick),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct ModifiedBindings(HashMap<(Modifiers, ClickBinding), ActionSequence>);
+
+impl<'de> Deserialize<'de> for ModifiedBindings {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let mut bindings = HashMap::new();
+
+        for (key, actions) in HashMap::<String, ActionSequence>::deserialize(deserializer)? {
+            let Some((modifier_names, binding_name)) = key.rsplit_once('+') else {
+                tracing::warn!(key, "ignoring unknown click binding");
+                continue;
+            };
+
+            let binding = ClickBinding::parse(binding_name)
+                .ok_or_else(|| D::Error::custom(format!("unknown click binding {binding_name:?} in {key:?}")))?;
+            let modifiers = modifier_names.split('+').try_fold(Modifiers::empty(), |modifiers, name| {
+                Modifiers::parse(name)
+                    .map(|modifier| modifiers | modifier)
+                    .ok_or_else(|| D::Error::custom(format!("unknown modifier {name:?} in {key:?}")))
+            })?;
+
+            bindings.insert((modifiers, binding), actions);
+        }
+
+        Ok(Self(bindings))
+    }
 }
 
 impl Default for ClickActions {
@@ -106,6 +303,73 @@ impl Default for ClickActions {
             double_click: default_double_click(),
             right_click: default_right_click(),
             middle_click: default_middle_click(),
+            double_click_focused: None,
+            double_click_unfocused: None,
+            right_click_focused: None,
+            right_click_unfocused: None,
+            middle_click_focused: None,
+            middle_click_unfocused: None,
+            scroll_up: default_none(),
+            scroll_down: default_none(),
+            scroll_left: default_none(),
+            scroll_right: default_none(),
+            long_press: default_none(),
+            swipe_up: default_none(),
+            swipe_down: default_none(),
+            swipe_left: default_none(),
+            swipe_right: default_none(),
+            hover: default_none(),
+            drag_hover: default_none(),
+            hover_delay_ms: default_hover_delay(),
+            modified: ModifiedBindings::default(),
+        }
+    }
+}
+
+impl ClickActions {
+    pub fn resolve(&self, binding: ClickBinding, modifiers: Modifiers) -> &ActionSequence {
+        let candidates = || std::iter::successors(Some(binding), |current| current.fallback());
+
+        if !modifiers.is_empty() {
+            if let Some(actions) = candidates().find_map(|candidate| self.modified.0.get(&(modifiers, candidate))) {
+                return actions;
+            }
+        }
+
+        candidates()
+            .find_map(|candidate| self.plain(candidate))
+            .unwrap_or(&self.left_click_unfocused)
+    }
+
+    pub fn hover_delay(&self) -> Duration {
+        Duration::from_millis(self.hover_delay_ms)
+    }
+
+    fn plain(&self, binding: ClickBinding) -> Option<&ActionSequence> {
+        match binding {
+            ClickBinding::LeftClick => None,
+            ClickBinding::LeftClickUnfocused => Some(&self.left_click_unfocused),
+            ClickBinding::LeftClickFocused => Some(&self.left_click_focused),
+            ClickBinding::DoubleClick => Some(&self.double_click),
+            ClickBinding::DoubleClickUnfocused => self.double_click_unfocused.as_ref(),
+            ClickBinding::DoubleClickFocused => self.double_click_focused.as_ref(),
+            ClickBinding::RightClick => Some(&self.right_click),
+            ClickBinding::RightClickUnfocused => self.right_click_unfocused.as_ref(),
+            ClickBinding::RightClickFocused => self.right_click_focused.as_ref(),
+            ClickBinding::MiddleClick => Some(&self.middle_click),
+            ClickBinding::MiddleClickUnfocused => self.middle_click_unfocused.as_ref(),
+            ClickBinding::MiddleClickFocused => self.middle_click_focused.as_ref(),
+            ClickBinding::ScrollUp => Some(&self.scroll_up),
+            ClickBinding::ScrollDown => Some(&self.scroll_down),
+            ClickBinding::ScrollLeft => Some(&self.scroll_left),
+            ClickBinding::ScrollRight => Some(&self.scroll_right),
+            ClickBinding::LongPress => Some(&self.long_press),
+            ClickBinding::SwipeUp => Some(&self.swipe_up),
+            ClickBinding::SwipeDown => Some(&self.swipe_down),
+            ClickBinding::SwipeLeft => Some(&self.swipe_left),
+            ClickBinding::SwipeRight => Some(&self.swipe_right),
+            ClickBinding::Hover => Some(&self.hover),
+            ClickBinding::DragHover => Some(&self.drag_hover),
         }
     }
 }
@@ -137,6 +401,137 @@ pub enum WindowAction {
     ToggleColumnTabbedDisplay,
     FocusWorkspacePrevious,
     Menu,
+    Niri(NiriAction),
+    Exec(CommandTemplate),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionSequence {
+    actions: Vec<WindowAction>,
+    stop_on_error: bool,
+    delay: Duration,
+}
+
+#[derive(Deserialize)]
+struct DetailedSequence {
+    actions: Vec<WindowAction>,
+    #[serde(default = "default_true")]
+    stop_on_error: bool,
+    #[serde(default)]
+    delay_ms: u64,
+}
+
+impl ActionSequence {
+    const MAX_DELAY_MS: u64 = 1000;
+
+    pub fn actions(&self) -> &[WindowAction] {
+        &self.actions
+    }
+
+    pub fn stop_on_error(&self) -> bool {
+        self.stop_on_error
+    }
+
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    pub fn opens_menu(&self) -> bool {
+        self.actions.contains(&WindowAction::Menu)
+    }
+
+    pub fn is_noop(&self) -> bool {
+        self.actions.iter().all(|action| matches!(action, WindowAction::None | WindowAction::Menu))
+    }
+}
+
+impl From<WindowAction> for ActionSequence {
+    fn from(action: WindowAction) -> Self {
+        Self {
+            actions: vec![action],
+            stop_on_error: true,
+            delay: Duration::ZERO,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ActionSequence {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let detailed = match value {
+            serde_json::Value::Array(_) => DetailedSequence {
+                actions: serde_json::from_value(value).map_err(D::Error::custom)?,
+                stop_on_error: true,
+                delay_ms: 0,
+            },
+            serde_json::Value::Object(ref fields) if fields.contains_key("actions") => {
+                serde_json::from_value(value).map_err(D::Error::custom)?
+            }
+            _ => return serde_json::from_value::<WindowAction>(value).map(Self::from).map_err(D::Error::custom),
+        };
+
+        if detailed.delay_ms > Self::MAX_DELAY_MS {
+            return Err(D::Error::custom(format!("delay_ms must be at most {}", Self::MAX_DELAY_MS)));
+        }
+
+        Ok(Self {
+            actions: detailed.actions,
+            stop_on_error: detailed.stop_on_error,
+            delay: Duration::from_millis(detailed.delay_ms),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NiriAction(serde_json::Value);
+
+impl NiriAction {
+    const WINDOW_FIELDS: [&str; 2] = ["id", "window_id"];
+
+    pub fn for_window(&self, window_id: u64) -> Result<(niri_ipc::Action, bool), serde_json::Error> {
+        let mut value = self.0.clone();
+        if let Some(name) = value.as_str() {
+            value = serde_json::json!({ name: {} });
+        }
+
+        if let Some(fields) = value.as_object_mut().and_then(|o| o.values_mut().next()) {
+            if fields.is_null() {
+                *fields = serde_json::json!({});
+            }
+            if let Some(fields) = fields.as_object_mut() {
+                for field in Self::WINDOW_FIELDS {
+                    if fields.get(field).is_none_or(serde_json::Value::is_null) {
+                        fields.insert(field.to_string(), window_id.into());
+                    }
+                }
+            }
+        }
+
+        let action: niri_ipc::Action = serde_json::from_value(value)?;
+        let targets_window = serde_json::to_value(&action)?
+            .as_object()
+            .and_then(|o| o.values().next())
+            .and_then(|fields| fields.as_object())
+            .is_some_and(|fields| Self::WINDOW_FIELDS.iter().any(|field| fields.contains_key(*field)));
+
+        Ok((action, targets_window))
+    }
+}
+
+impl<'de> Deserialize<'de> for NiriAction {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let action = Self(serde_json::Value::deserialize(deserializer)?);
+        action.for_window(0).map_err(serde::de::Error::custom)?;
+        Ok(action)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -154,9 +549,103 @@ pub struct IgnoreRule {
 }
 
 #[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "MenuItemConfig")]
 pub struct ContextMenuItem {
     pub label: String,
-    pub action: WindowAction,
+    pub kind: MenuItemKind,
+    pub action: ActionSequence,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuItemKind {
+    Action,
+    Submenu(MenuSource),
+    Check(WindowProperty),
+    Pin,
+    Separator,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowProperty {
+    IsFloating,
+    IsUrgent,
+    IsStacked,
+    IsTabbed,
+    OnFocusedOutput,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum MenuItemType {
+    #[default]
+    Item,
+    Check,
+    Pin,
+    Separator,
+}
+
+#[derive(Deserialize)]
+struct MenuItemConfig {
+    #[serde(default, rename = "type")]
+    item_type: MenuItemType,
+    #[serde(default)]
+    label: String,
+    #[serde(default = "default_none")]
+    action: ActionSequence,
+    #[serde(default)]
+    submenu: Option<MenuSource>,
+    #[serde(default)]
+    checked: Option<WindowProperty>,
+}
+
+impl TryFrom<MenuItemConfig> for ContextMenuItem {
+    type Error = String;
+
+    fn try_from(config: MenuItemConfig) -> Result<Self, Self::Error> {
+        let kind = match (config.item_type, config.submenu, config.checked) {
+            (MenuItemType::Item, None, None) => MenuItemKind::Action,
+            (MenuItemType::Item, Some(source), None) => MenuItemKind::Submenu(source),
+            (MenuItemType::Check, None, Some(property)) => MenuItemKind::Check(property),
+            (MenuItemType::Check, None, None) => return Err("check items need a `checked` window property".into()),
+            (MenuItemType::Pin, None, None) => MenuItemKind::Pin,
+            (MenuItemType::Separator, None, None) => MenuItemKind::Separator,
+            _ => {
+                return Err(format!(
+                    "{:?}: `submenu` only applies to plain items and `checked` only to check items",
+                    config.label
+                ))
+            }
+        };
+
+        Ok(Self { label: config.label, kind, action: config.action })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupClick {
+    #[default]
+    Cycle,
+    List,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Layout,
+    AppId,
+    Opened,
+    Recent,
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MenuSource {
+    Workspaces,
+    Outputs,
 }
 
 fn parse_regex<'de, D>(deserializer: D) -> Result<Regex, D::Error>
@@ -184,29 +673,40 @@ fn default_max_taskbar() -> i32 { 1200 }
 fn default_scroll_arrow_left() -> String { "◀".to_string() }
 fn default_scroll_arrow_right() -> String { "▶".to_string() }
 
-fn default_left_unfocused() -> WindowAction { WindowAction::FocusWindow }
-fn default_left_focused() -> WindowAction { WindowAction::MaximizeColumn }
-fn default_double_click() -> WindowAction { WindowAction::MaximizeWindowToEdges }
-fn default_right_click() -> WindowAction { WindowAction::Menu }
-fn default_middle_click() -> WindowAction { WindowAction::CloseWindow }
+fn default_left_unfocused() -> ActionSequence { WindowAction::FocusWindow.into() }
+fn default_left_focused() -> ActionSequence { WindowAction::MaximizeColumn.into() }
+fn default_double_click() -> ActionSequence { WindowAction::MaximizeWindowToEdges.into() }
+fn default_right_click() -> ActionSequence { WindowAction::Menu.into() }
+fn default_middle_click() -> ActionSequence { WindowAction::CloseWindow.into() }
+fn default_none() -> ActionSequence { WindowAction::None.into() }
+fn default_hover_delay() -> u64 { 600 }
 
 fn default_context_menu() -> Vec<ContextMenuItem> {
     vec![
         ContextMenuItem {
             label: "  Maximize Column".to_string(),
-            action: WindowAction::MaximizeColumn,
+            action: WindowAction::MaximizeColumn.into(),
+            kind: MenuItemKind::Action,
         },
         ContextMenuItem {
             label: "  Maximize to Edges".to_string(),
-            action: WindowAction::MaximizeWindowToEdges,
+            action: WindowAction::MaximizeWindowToEdges.into(),
+            kind: MenuItemKind::Action,
         },
         ContextMenuItem {
             label: "󰉩  Toggle Floating".to_string(),
-            action: WindowAction::ToggleWindowFloating,
+            action: WindowAction::ToggleWindowFloating.into(),
+            kind: MenuItemKind::Action,
+        },
+        ContextMenuItem {
+            label: "  Pin to Bar".to_string(),
+            kind: MenuItemKind::Pin,
+            action: WindowAction::None.into(),
         },
         ContextMenuItem {
             label: "  Close Window".to_string(),
-            action: WindowAction::CloseWindow,
+            action: WindowAction::CloseWindow.into(),
+            kind: MenuItemKind::Action,
         },
     ]
 }
@@ -345,4 +845,40 @@ impl Settings {
     pub fn context_menu(&self) -> &[ContextMenuItem] {
         &self.context_menu
     }
+
+    pub fn context_menu_desktop_actions(&self) -> bool {
+        self.context_menu_desktop_actions
+    }
+
+    pub fn pinned(&self) -> &[String] {
+        &self.pinned
+    }
+
+    pub fn group_by_app(&self) -> bool {
+        self.group_by_app
+    }
+
+    pub fn group_click(&self) -> GroupClick {
+        self.group_click
+    }
+
+    pub fn workspace_sections(&self) -> bool {
+        self.workspace_sections
+    }
+
+    pub fn sort_by(&self) -> SortOrder {
+        self.sort_by
+    }
+
+    pub fn sort_by_workspace(&self) -> bool {
+        self.sort_by_workspace
+    }
+
+    pub fn debug_record_events(&self) -> Option<&Path> {
+        self.debug_record_events.as_deref()
+    }
+
+    pub fn debug_replay_events(&self) -> Option<&Path> {
+        self.debug_replay_events.as_deref()
+    }
 }
\ No newline at end of file
@@ -0,0 +1,134 @@
+use serde_json::json;
+use super::*;
+
+fn click_actions(value: serde_json::Value) -> ClickActions {
+    serde_json::from_value(value).expect("click actions")
+}
+
+fn first_action(actions: &ActionSequence) -> &WindowAction {
+    &actions.actions()[0]
+}
+
+#[test]
+fn modified_binding_overrides_plain_binding() {
+    let actions = click_actions(json!({
+        "middle_click": "close-window",
+        "ctrl+middle_click": "toggle-window-floating",
+        "ctrl+shift+middle_click": "fullscreen-window",
+    }));
+
+    assert_eq!(first_action(actions.resolve(ClickBinding::MiddleClick, Modifiers::empty())), &WindowAction::CloseWindow);
+    assert_eq!(first_action(actions.resolve(ClickBinding::MiddleClick, Modifiers::CTRL)), &WindowAction::ToggleWindowFloating);
+    assert_eq!(
+        first_action(actions.resolve(ClickBinding::MiddleClick, Modifiers::CTRL | Modifiers::SHIFT)),
+        &WindowAction::FullscreenWindow
+    );
+}
+
+#[test]
+fn unconfigured_modifier_falls_back_to_plain_binding() {
+    let actions = click_actions(json!({ "ctrl+right_click": "close-window" }));
+
+    assert_eq!(first_action(actions.resolve(ClickBinding::RightClick, Modifiers::ALT)), &WindowAction::Menu);
+    assert_eq!(first_action(actions.resolve(ClickBinding::RightClick, Modifiers::CTRL | Modifiers::ALT)), &WindowAction::Menu);
+}
+
+#[test]
+fn left_click_modifier_applies_regardless_of_focus() {
+    let actions = click_actions(json!({
+        "shift+left_click": "close-window",
+        "shift+left_click_focused": "center-column",
+    }));
+
+    assert_eq!(first_action(actions.resolve(ClickBinding::LeftClickUnfocused, Modifiers::SHIFT)), &WindowAction::CloseWindow);
+    assert_eq!(first_action(actions.resolve(ClickBinding::LeftClickFocused, Modifiers::SHIFT)), &WindowAction::CenterColumn);
+    assert_eq!(first_action(actions.resolve(ClickBinding::LeftClickFocused, Modifiers::empty())), &WindowAction::MaximizeColumn);
+}
+
+#[test]
+fn modifier_names_are_validated() {
+    let actions = click_actions(json!({ "Super+Control+scroll_up": "focus-window" }));
+    assert_eq!(
+        first_action(actions.resolve(ClickBinding::ScrollUp, Modifiers::SUPER | Modifiers::CTRL)),
+        &WindowAction::FocusWindow
+    );
+
+    assert!(serde_json::from_value::<ClickActions>(json!({ "hyper+left_click": "none" })).is_err());
+    assert!(serde_json::from_value::<ClickActions>(json!({ "shift+triple_click": "none" })).is_err());
+}
+
+#[test]
+fn focus_variants_fall_back_to_single_binding() {
+    let actions = click_actions(json!({
+        "right_click": "close-window",
+        "right_click_focused": "fullscreen-window",
+        "middle_click_unfocused": "focus-window",
+        "shift+double_click_unfocused": "center-window",
+    }));
+
+    let resolve = |binding: ClickBinding, focused, modifiers| first_action(actions.resolve(binding.with_focus(focused), modifiers)).clone();
+
+    assert_eq!(resolve(ClickBinding::RightClick, true, Modifiers::empty()), WindowAction::FullscreenWindow);
+    assert_eq!(resolve(ClickBinding::RightClick, false, Modifiers::empty()), WindowAction::CloseWindow);
+    assert_eq!(resolve(ClickBinding::MiddleClick, false, Modifiers::empty()), WindowAction::FocusWindow);
+    assert_eq!(resolve(ClickBinding::MiddleClick, true, Modifiers::empty()), WindowAction::CloseWindow);
+    assert_eq!(resolve(ClickBinding::DoubleClick, false, Modifiers::SHIFT), WindowAction::CenterWindow);
+    assert_eq!(resolve(ClickBinding::DoubleClick, true, Modifiers::SHIFT), WindowAction::MaximizeWindowToEdges);
+}
+
+#[test]
+fn hover_bindings_default_to_none_with_dwell_time() {
+    let defaults = click_actions(json!({}));
+    assert!(defaults.hover.is_noop());
+    assert!(defaults.drag_hover.is_noop());
+    assert_eq!(defaults.hover_delay(), Duration::from_millis(600));
+
+    let actions = click_actions(json!({ "hover": "focus-window", "hover_delay_ms": 250 }));
+    assert_eq!(first_action(actions.resolve(ClickBinding::Hover, Modifiers::empty())), &WindowAction::FocusWindow);
+    assert_eq!(actions.hover_delay(), Duration::from_millis(250));
+}
+
+#[test]
+fn context_menu_items_can_be_submenus() {
+    let settings: Settings = serde_json::from_value(json!({
+        "context_menu": [
+            {"label": "Move to workspace", "submenu": "workspaces"},
+            {"label": "Move to output", "submenu": "outputs"},
+            {"label": "Close", "action": "close-window"},
+        ]
+    }))
+    .unwrap();
+
+    let menu = settings.context_menu();
+    assert_eq!(menu[0].kind, MenuItemKind::Submenu(MenuSource::Workspaces));
+    assert!(menu[0].action.is_noop());
+    assert_eq!(menu[1].kind, MenuItemKind::Submenu(MenuSource::Outputs));
+    assert_eq!(menu[2].kind, MenuItemKind::Action);
+    assert_eq!(first_action(&menu[2].action), &WindowAction::CloseWindow);
+
+    assert!(serde_json::from_value::<ContextMenuItem>(json!({"label": "x", "submenu": "windows"})).is_err());
+}
+
+#[test]
+fn check_items_and_separators() {
+    let menu: Vec<ContextMenuItem> = serde_json::from_value(json!([
+        {"type": "check", "label": "Floating", "checked": "is_floating", "action": "toggle-window-floating"},
+        {"type": "separator"},
+        {"type": "check", "label": "Tabbed", "checked": "is_tabbed", "action": "toggle-column-tabbed-display"},
+    ]))
+    .unwrap();
+
+    assert_eq!(menu[0].kind, MenuItemKind::Check(WindowProperty::IsFloating));
+    assert_eq!(first_action(&menu[0].action), &WindowAction::ToggleWindowFloating);
+    assert_eq!(menu[1].kind, MenuItemKind::Separator);
+    assert_eq!(menu[2].kind, MenuItemKind::Check(WindowProperty::IsTabbed));
+
+    for invalid in [
+        json!({"type": "check", "label": "Floating"}),
+        json!({"type": "check", "label": "x", "checked": "is_maximized"}),
+        json!({"label": "x", "submenu": "outputs", "checked": "is_urgent"}),
+        json!({"type": "separator", "submenu": "outputs"}),
+    ] {
+        assert!(serde_json::from_value::<ContextMenuItem>(invalid.clone()).is_err(), "{invalid}");
+    }
+}
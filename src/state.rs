@@ -0,0 +1,45 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use crate::errors::ModuleError;
+
+/// Path of a file under `$XDG_STATE_HOME/niri_window_buttons`, or `~/.local/state` without it.
+pub fn state_file(name: &str) -> Option<PathBuf> {
+    let state_home = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+
+    Some(state_home.join("niri_window_buttons").join(name))
+}
+
+/// Reads a JSON state file, or `None` if it doesn't exist yet.
+pub fn read<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, ModuleError> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(source) => return Err(ModuleError::StateFile { path: path.to_path_buf(), source }),
+    };
+
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|source| ModuleError::InvalidStateFile { path: path.to_path_buf(), source })
+}
+
+/// Replaces a JSON state file atomically, creating its directory as needed.
+pub fn write<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), ModuleError> {
+    let error = |source| ModuleError::StateFile { path: path.to_path_buf(), source };
+    // Several bars may save the same file at once, so each write stages to its own file.
+    static STAGED: AtomicU64 = AtomicU64::new(0);
+    let staging = path.with_extension(format!("json.{}.{}.tmp", std::process::id(), STAGED.fetch_add(1, Ordering::Relaxed)));
+
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory).map_err(error)?;
+    }
+    let content = serde_json::to_string_pretty(value).expect("state always serializes");
+    fs::write(&staging, content).map_err(error)?;
+    fs::rename(&staging, path).map_err(error)
+}
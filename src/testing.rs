@@ -0,0 +1,245 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+use niri_ipc::{Action, Event, Output, Reply, Request, Response, Window, WindowLayout, Workspace};
+
+pub fn parse_fixture(fixture: &str) -> Vec<Event> {
+    fixture
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|e| panic!("invalid fixture event {line}: {e}")))
+        .collect()
+}
+
+pub fn window(id: u64, workspace_id: u64, pos: Option<(usize, usize)>) -> Window {
+    Window {
+        id,
+        title: Some(format!("window {id}")),
+        app_id: Some(format!("app.{id}")),
+        pid: Some(1000 + id as i32),
+        workspace_id: Some(workspace_id),
+        is_focused: false,
+        is_floating: pos.is_none(),
+        is_urgent: false,
+        layout: WindowLayout {
+            pos_in_scrolling_layout: pos,
+            tile_size: (800.0, 600.0),
+            window_size: (800, 600),
+            tile_pos_in_workspace_view: None,
+            window_offset_in_tile: (0.0, 0.0),
+        },
+        focus_timestamp: None,
+    }
+}
+
+pub fn workspace(id: u64, idx: u8, output: &str, is_active: bool) -> Workspace {
+    Workspace {
+        id,
+        idx,
+        name: None,
+        output: Some(output.to_string()),
+        is_urgent: false,
+        is_active,
+        is_focused: false,
+        active_window_id: None,
+    }
+}
+
+/// A fresh directory under the system temp dir, unique per call.
+pub fn scratch_dir() -> PathBuf {
+    static INSTANCE: AtomicUsize = AtomicUsize::new(0);
+
+    let directory = std::env::temp_dir().join(format!(
+        "niri-window-buttons-{}-{}",
+        std::process::id(),
+        INSTANCE.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&directory).expect("create scratch directory");
+    directory
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    windows: Vec<Window>,
+    workspaces: Vec<Workspace>,
+    outputs: HashMap<String, Output>,
+    events: Vec<Event>,
+    actions: Vec<Action>,
+    failing_action: Option<String>,
+}
+
+pub struct MockNiri {
+    directory: PathBuf,
+    socket_path: PathBuf,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Arc<AtomicBool>,
+    stream_generation: Arc<AtomicUsize>,
+    connections: Arc<AtomicUsize>,
+}
+
+impl MockNiri {
+    pub fn start() -> Self {
+        let directory = scratch_dir();
+        let socket_path = directory.join("niri.sock");
+        let listener = UnixListener::bind(&socket_path).expect("bind mock niri socket");
+        listener.set_nonblocking(true).expect("nonblocking mock listener");
+
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let stream_generation = Arc::new(AtomicUsize::new(0));
+        let connections = Arc::new(AtomicUsize::new(0));
+
+        let server_state = state.clone();
+        let server_shutdown = shutdown.clone();
+        let server_generation = stream_generation.clone();
+        let server_connections = connections.clone();
+        std::thread::spawn(move || {
+            while !server_shutdown.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        server_connections.fetch_add(1, Ordering::Relaxed);
+                        let state = server_state.clone();
+                        let shutdown = server_shutdown.clone();
+                        let generation = server_generation.clone();
+                        std::thread::spawn(move || serve_client(stream, state, shutdown, generation));
+                    }
+                    Err(_) => std::thread::sleep(Duration::from_millis(5)),
+                }
+            }
+        });
+
+        Self { directory, socket_path, state, shutdown, stream_generation, connections }
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    pub fn with_windows(self, windows: Vec<Window>) -> Self {
+        self.state.lock().unwrap().windows = windows;
+        self
+    }
+
+    pub fn with_workspaces(self, workspaces: Vec<Workspace>) -> Self {
+        self.state.lock().unwrap().workspaces = workspaces;
+        self
+    }
+
+    pub fn with_events(self, events: Vec<Event>) -> Self {
+        self.state.lock().unwrap().events = events;
+        self
+    }
+
+    pub fn failing_action(self, action_name: &str) -> Self {
+        self.state.lock().unwrap().failing_action = Some(action_name.to_string());
+        self
+    }
+
+    pub fn actions(&self) -> Vec<serde_json::Value> {
+        self.state
+            .lock()
+            .unwrap()
+            .actions
+            .iter()
+            .map(|action| serde_json::to_value(action).expect("serialize action"))
+            .collect()
+    }
+
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::Relaxed)
+    }
+
+    pub fn clear_actions(&self) {
+        self.state.lock().unwrap().actions.clear();
+    }
+
+    pub fn drop_event_streams(&self) {
+        self.stream_generation.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Drop for MockNiri {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        let _ = std::fs::remove_dir_all(&self.directory);
+    }
+}
+
+fn serve_client(
+    stream: UnixStream,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Arc<AtomicBool>,
+    generation: Arc<AtomicUsize>,
+) {
+    let mut writer = stream.try_clone().expect("clone mock client stream");
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+
+        let request: Request = serde_json::from_str(&line).expect("mock received invalid request");
+        let reply: Reply = {
+            let mut state = state.lock().unwrap();
+            match request {
+                Request::Windows => Ok(Response::Windows(state.windows.clone())),
+                Request::Workspaces => Ok(Response::Workspaces(state.workspaces.clone())),
+                Request::Outputs => Ok(Response::Outputs(state.outputs.clone())),
+                Request::Action(action) => {
+                    let value = serde_json::to_value(&action).expect("serialize action");
+                    let name = value.as_object().and_then(|o| o.keys().next().cloned()).unwrap_or_default();
+                    state.actions.push(action);
+                    if state.failing_action.as_deref() == Some(name.as_str()) {
+                        Err(format!("{name} failed"))
+                    } else {
+                        Ok(Response::Handled)
+                    }
+                }
+                Request::EventStream => {
+                    let events = state.events.clone();
+                    drop(state);
+                    stream_events(&mut writer, events, &shutdown, &generation);
+                    return;
+                }
+                _ => Err("unsupported by mock".to_string()),
+            }
+        };
+
+        let mut response = serde_json::to_string(&reply).expect("serialize reply");
+        response.push('\n');
+        if writer.write_all(response.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+fn stream_events(writer: &mut UnixStream, events: Vec<Event>, shutdown: &AtomicBool, generation: &AtomicUsize) {
+    let started_generation = generation.load(Ordering::Relaxed);
+    let mut output = serde_json::to_string(&Ok::<_, String>(Response::Handled)).expect("serialize reply");
+    output.push('\n');
+
+    for event in events {
+        output.push_str(&serde_json::to_string(&event).expect("serialize event"));
+        output.push('\n');
+    }
+
+    if writer.write_all(output.as_bytes()).is_err() {
+        return;
+    }
+
+    while !shutdown.load(Ordering::Relaxed) && generation.load(Ordering::Relaxed) == started_generation {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
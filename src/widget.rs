@@ -1,11 +1,32 @@
-use std::{cell::RefCell, fmt::Debug, path::PathBuf, rc::Rc, time::{Duration, Instant}};
+use std::{cell::{Cell, RefCell}, fmt::Debug, path::PathBuf, rc::Rc, time::{Duration, Instant}};
+use itertools::Itertools;
+use niri_ipc::{Output, Workspace};
 use waybar_cffi::gtk::{
-    self as gtk, CssProvider, IconLookupFlags, IconSize, IconTheme, Menu, MenuItem, Orientation, ReliefStyle,
+    self as gtk, CssProvider, gio, IconLookupFlags, IconSize, IconTheme, Menu, MenuItem, Orientation, ReliefStyle,
     gdk_pixbuf::Pixbuf,
-    prelude::{BoxExt, ButtonExt, Cast, ContainerExt, CssProviderExt, DragContextExtManual, GdkPixbufExt, GtkMenuExt, GtkMenuItemExt, IconThemeExt, LabelExt, MenuShellExt, StyleContextExt, WidgetExt, WidgetExtManual},
+    prelude::{BoxExt, ButtonExt, Cast, CheckMenuItemExt, ContainerExt, CssProviderExt, DragContextExtManual, GdkPixbufExt, GtkMenuExt, GtkMenuItemExt, IconThemeExt, LabelExt, MenuShellExt, StyleContextExt, WidgetExt, WidgetExtManual},
     DestDefaults, TargetEntry, TargetFlags,
 };
-use crate::global::SharedState;
+use crate::{
+    actions::ActionRequest,
+    compositor::{DropZone, WindowInfo},
+    global::SharedState,
+    settings::{ActionSequence, ClickActions, ClickBinding, MenuItemKind, MenuSource, Modifiers, SortOrder},
+};
+
+mod column;
+mod group;
+mod launcher;
+mod section;
+#[cfg(test)]
+mod tests;
+
+pub use column::ColumnBox;
+pub use group::GroupButton;
+pub use launcher::LauncherButton;
+pub use section::SectionLabel;
+
+const ACTION_FAILED_HIGHLIGHT: Duration = Duration::from_millis(1500);
 
 pub struct WindowButton {
     app_id: Option<String>,
@@ -15,7 +36,16 @@ pub struct WindowButton {
     display_titles: bool,
     state: SharedState,
     window_id: u64,
+    window: Rc<RefCell<WindowInfo>>,
     title: Rc<RefCell<Option<String>>>,
+    is_focused: Rc<Cell<bool>>,
+    suppress_click: Rc<Cell<bool>>,
+    gestures: Rc<TouchGestures>,
+}
+
+struct TouchGestures {
+    long_press: gtk::GestureLongPress,
+    swipe: gtk::GestureSwipe,
 }
 
 impl Debug for WindowButton {
@@ -29,6 +59,9 @@ impl Debug for WindowButton {
 }
 
 thread_local! {
+    /// The window whose button is being dragged, shared by every bar in this Waybar.
+    static DRAGGED: RefCell<Option<WindowInfo>> = const { RefCell::new(None) };
+
     static BUTTON_STYLES: CssProvider = {
         let provider = CssProvider::new();
         if let Err(e) = provider.load_from_data(include_bytes!("styles.css")) {
@@ -42,7 +75,7 @@ thread_local! {
 
 impl WindowButton {
     #[tracing::instrument(level = "TRACE", fields(app_id = &window.app_id))]
-    pub fn create(state: &SharedState, window: &niri_ipc::Window) -> Self {
+    pub fn create(state: &SharedState, window: &WindowInfo) -> Self {
         let state_clone = state.clone();
         let display_titles = state.settings().show_window_titles();
 
@@ -74,6 +107,11 @@ impl WindowButton {
         let app_id = window.app_id.clone();
         let icon_location = app_id.as_deref().and_then(|id| state_clone.icon_resolver().resolve(id));
 
+        let gestures = Rc::new(TouchGestures {
+            long_press: gtk::GestureLongPress::new(&gtk_button),
+            swipe: gtk::GestureSwipe::new(&gtk_button),
+        });
+
         let button = Self {
             app_id,
             gtk_button,
@@ -82,18 +120,32 @@ impl WindowButton {
             display_titles,
             state: state_clone,
             window_id: window.id,
+            window: Rc::new(RefCell::new(window.clone())),
             title: Rc::new(RefCell::new(window.title.clone())),
+            is_focused: Rc::new(Cell::new(window.is_focused)),
+            suppress_click: Rc::new(Cell::new(false)),
+            gestures,
         };
 
+        // Hover handlers go first so their press handler cancels a pending dwell
+        // before the click handlers stop propagation of middle and right clicks.
+        button.setup_hover_handlers(window.id);
         button.setup_click_handlers(window.id);
+        button.setup_scroll_handler(window.id);
+        button.setup_touch_gestures(window.id);
         button.setup_drag_reorder();
         button.setup_icon_rendering(icon_location);
 
         button
     }
 
+    pub fn update_window(&self, window: &WindowInfo) {
+        self.window.replace(window.clone());
+    }
+
     #[tracing::instrument(level = "TRACE")]
     pub fn update_focus(&self, is_focused: bool) {
+        self.is_focused.set(is_focused);
         let style_ctx = self.gtk_button.style_context();
         if is_focused {
             style_ctx.add_class("focused");
@@ -104,6 +156,19 @@ impl WindowButton {
         self.gtk_button.queue_draw();
     }
 
+    /// Marks the button's place within a column container, or clears it with `None`.
+    pub fn update_tile(&self, position: Option<(bool, bool)>) {
+        let (first, last) = position.unwrap_or_default();
+        let style_ctx = self.gtk_button.style_context();
+        for (class, enabled) in [("tile-first", first), ("tile-last", last)] {
+            if enabled {
+                style_ctx.add_class(class);
+            } else {
+                style_ctx.remove_class(class);
+            }
+        }
+    }
+
     #[tracing::instrument(level = "TRACE")]
     pub fn update_title(&self, title: Option<&str>) {
         if let Some(t) = title {
@@ -149,211 +214,481 @@ impl WindowButton {
 
 	fn setup_click_handlers(&self, window_id: u64) {
 		let state = self.state.clone();
-		let state_middle = self.state.clone();
-		let state_right = self.state.clone();
+		let state_press = self.state.clone();
 		let button_ref = self.gtk_button.clone();
-		let last_click_time = Rc::new(RefCell::new(Instant::now() - Duration::from_secs(1)));
+		let last_click: Rc<Cell<Option<(Instant, bool)>>> = Rc::new(Cell::new(None));
 		let app_id = self.app_id.clone();
-		let app_id_middle = self.app_id.clone();
-		let app_id_right = self.app_id.clone();
+		let app_id_press = self.app_id.clone();
 		let title = self.title.clone();
+		let is_focused = self.is_focused.clone();
+		let suppress_click = self.suppress_click.clone();
 
 		let title_clone = title.clone();
 		self.gtk_button.connect_clicked(move |_| {
-		    let is_currently_focused = button_ref.style_context().has_class("focused");
+		    if suppress_click.replace(false) {
+		        return;
+		    }
+
+		    let focused = is_focused.get();
+		    let modifiers = gtk::current_event_state().map(modifiers_from).unwrap_or_else(Modifiers::empty);
 		    let actions = state.settings().get_click_actions(
 		        app_id.as_deref(),
 		        title_clone.borrow().as_deref()
 		    );
 
-		    if is_currently_focused {
-		        let mut last_click = last_click_time.borrow_mut();
-		        let now = Instant::now();
-		        let time_since_last = now.duration_since(*last_click);
-		        
-		        if time_since_last < Duration::from_millis(300) {
-		            Self::execute_action(&state, window_id, &actions.double_click);
-		            *last_click = Instant::now() - Duration::from_secs(1);
-		        } else {
-		            Self::execute_action(&state, window_id, &actions.left_click_focused);
-		            *last_click = now;
+		    let now = Instant::now();
+		    let binding = match last_click.take() {
+		        Some((previous, was_focused)) if now.duration_since(previous) < Duration::from_millis(300) => {
+		            ClickBinding::DoubleClick.with_focus(was_focused)
 		        }
-		    } else {
-		        Self::execute_action(&state, window_id, &actions.left_click_unfocused);
-		    }
+		        _ => {
+		            last_click.set(Some((now, focused)));
+		            ClickBinding::LeftClick.with_focus(focused)
+		        }
+		    };
+
+		    Self::execute_action(&state, &button_ref, window_id, actions.resolve(binding, modifiers));
 		});
 
 		let menu_self = self.clone_for_menu();
-		let title_middle = title.clone();
-		self.gtk_button.connect_button_press_event(move |_, event| {
-		    if event.button() == 2 {
-		        let actions = state_middle.settings().get_click_actions(
-		            app_id_middle.as_deref(),
-		            title_middle.borrow().as_deref()
-		        );
-		        if actions.middle_click == crate::settings::WindowAction::Menu {
-		            menu_self.display_context_menu(window_id);
-		        } else {
-		            Self::execute_action(&state_middle, window_id, &actions.middle_click);
-		        }
-		        gtk::glib::Propagation::Stop
-		    } else if event.button() == 3 {
-		        let actions = state_right.settings().get_click_actions(
-		            app_id_right.as_deref(),
-		            title_middle.borrow().as_deref()
-		        );
-		        if actions.right_click == crate::settings::WindowAction::Menu {
-		            menu_self.display_context_menu(window_id);
-		        } else {
-		            Self::execute_action(&state_right, window_id, &actions.right_click);
-		        }
-		        gtk::glib::Propagation::Stop
-		    } else {
-		        gtk::glib::Propagation::Proceed
+		let is_focused = self.is_focused.clone();
+		self.gtk_button.connect_button_press_event(move |button, event| {
+		    let binding = match event.button() {
+		        2 => ClickBinding::MiddleClick,
+		        3 => ClickBinding::RightClick,
+		        _ => return gtk::glib::Propagation::Proceed,
+		    };
+
+		    let actions = state_press.settings().get_click_actions(
+		        app_id_press.as_deref(),
+		        title.borrow().as_deref()
+		    );
+		    let sequence = actions.resolve(binding.with_focus(is_focused.get()), modifiers_from(event.state()));
+		    if sequence.opens_menu() {
+		        menu_self.display_context_menu(window_id);
 		    }
+		    Self::execute_action(&state_press, button, window_id, sequence);
+		    gtk::glib::Propagation::Stop
 		});
 	}
 
-    fn execute_action(state: &SharedState, window_id: u64, action: &crate::settings::WindowAction) {
-        use crate::settings::WindowAction;
-        match action {
-            WindowAction::None => {}
-            WindowAction::FocusWindow => {
-                if let Err(e) = state.compositor().focus_window(window_id) {
-                    tracing::warn!(%e, id = window_id, "focus failed");
-                }
-            }
-            WindowAction::CloseWindow => {
-                if let Err(e) = state.compositor().close_window(window_id) {
-                    tracing::warn!(%e, id = window_id, "close failed");
-                }
-            }
-            WindowAction::MaximizeColumn => {
-                if let Err(e) = state.compositor().maximize_window_column(window_id) {
-                    tracing::warn!(%e, id = window_id, "maximize column failed");
-                }
-            }
-            WindowAction::MaximizeWindowToEdges => {
-                if let Err(e) = state.compositor().maximize_window_to_edges(window_id) {
-                    tracing::warn!(%e, id = window_id, "maximize to edges failed");
-                }
-            }
-            WindowAction::CenterColumn => {
-                if let Err(e) = state.compositor().center_column(window_id) {
-                    tracing::warn!(%e, id = window_id, "center column failed");
-                }
-            }
-            WindowAction::CenterWindow => {
-                if let Err(e) = state.compositor().center_window(window_id) {
-                    tracing::warn!(%e, id = window_id, "center window failed");
-                }
-            }
-            WindowAction::CenterVisibleColumns => {
-                if let Err(e) = state.compositor().center_visible_columns(window_id) {
-                    tracing::warn!(%e, id = window_id, "center visible columns failed");
-                }
-            }
-            WindowAction::ExpandColumnToAvailableWidth => {
-                if let Err(e) = state.compositor().expand_column_to_available_width(window_id) {
-                    tracing::warn!(%e, id = window_id, "expand column failed");
-                }
-            }
-            WindowAction::FullscreenWindow => {
-                if let Err(e) = state.compositor().fullscreen_window(window_id) {
-                    tracing::warn!(%e, id = window_id, "fullscreen failed");
+    fn setup_scroll_handler(&self, window_id: u64) {
+        use gtk::gdk::{EventMask, ScrollDirection};
+
+        self.gtk_button.add_events(EventMask::SCROLL_MASK | EventMask::SMOOTH_SCROLL_MASK);
+
+        let state = self.state.clone();
+        let app_id = self.app_id.clone();
+        let title = self.title.clone();
+        let accumulator = RefCell::new(ScrollAccumulator::default());
+
+        self.gtk_button.connect_scroll_event(move |button, event| {
+            let actions = state.settings().get_click_actions(app_id.as_deref(), title.borrow().as_deref());
+            let modifiers = modifiers_from(event.state());
+
+            let direction = match event.direction() {
+                ScrollDirection::Smooth => {
+                    let (dx, dy) = event.delta();
+                    let axis = if dy.abs() >= dx.abs() {
+                        [ScrollDirection::Up, ScrollDirection::Down]
+                    } else {
+                        [ScrollDirection::Left, ScrollDirection::Right]
+                    };
+                    let bound = axis
+                        .into_iter()
+                        .any(|direction| scroll_binding(&actions, direction, modifiers).is_some_and(|b| !b.is_noop()));
+                    if !bound {
+                        return gtk::glib::Propagation::Proceed;
+                    }
+
+                    match accumulator.borrow_mut().push(dx, dy) {
+                        Some(direction) => direction,
+                        None => return gtk::glib::Propagation::Stop,
+                    }
                 }
-            }
-            WindowAction::ToggleWindowedFullscreen => {
-                if let Err(e) = state.compositor().toggle_windowed_fullscreen(window_id) {
-                    tracing::warn!(%e, id = window_id, "toggle windowed fullscreen failed");
+                direction => direction,
+            };
+
+            match scroll_binding(&actions, direction, modifiers) {
+                Some(binding) if !binding.is_noop() => {
+                    Self::execute_action(&state, button, window_id, binding);
+                    gtk::glib::Propagation::Stop
                 }
+                _ => gtk::glib::Propagation::Proceed,
             }
-            WindowAction::ToggleWindowFloating => {
-                if let Err(e) = state.compositor().toggle_floating(window_id) {
-                    tracing::warn!(%e, id = window_id, "toggle floating failed");
+        });
+    }
+
+    fn setup_touch_gestures(&self, window_id: u64) {
+        use gtk::prelude::{EventControllerExt, GestureExt, GestureSingleExt};
+
+        let drag_suspended = Rc::new(Cell::new(false));
+        let resume_drag = {
+            let drag_suspended = drag_suspended.clone();
+            move |gesture: &gtk::Gesture| {
+                if drag_suspended.replace(false) {
+                    if let Some(widget) = gesture.widget() {
+                        enable_drag_source(&widget);
+                    }
                 }
             }
-            WindowAction::ConsumeWindowIntoColumn => {
-                if let Err(e) = state.compositor().consume_window_into_column(window_id) {
-                    tracing::warn!(%e, id = window_id, "consume window into column failed");
-                }
+        };
+
+        let long_press = &self.gestures.long_press;
+        long_press.set_propagation_phase(gtk::PropagationPhase::Capture);
+
+        let suppress_click = self.suppress_click.clone();
+        long_press.connect_begin(move |_, _| {
+            suppress_click.set(false);
+        });
+
+        let state = self.state.clone();
+        let app_id = self.app_id.clone();
+        let title = self.title.clone();
+        let suppress_click = self.suppress_click.clone();
+        let suspend = drag_suspended.clone();
+        let menu_self = self.clone_for_menu();
+        long_press.connect_pressed(move |gesture, _, _| {
+            let Some(button) = gesture.widget().and_then(|w| w.downcast::<gtk::Button>().ok()) else {
+                return;
+            };
+
+            let actions = state.settings().get_click_actions(app_id.as_deref(), title.borrow().as_deref());
+            let modifiers = gesture.last_event(gesture.current_sequence().as_ref())
+                .and_then(|event| event.state())
+                .map(modifiers_from)
+                .unwrap_or_else(Modifiers::empty);
+            let binding = actions.resolve(ClickBinding::LongPress, modifiers);
+            if binding.is_noop() && !binding.opens_menu() {
+                return;
             }
-            WindowAction::ExpelWindowFromColumn => {
-                if let Err(e) = state.compositor().expel_window_from_column(window_id) {
-                    tracing::warn!(%e, id = window_id, "expel window from column failed");
-                }
+
+            suppress_click.set(true);
+            button.drag_source_unset();
+            suspend.set(true);
+            if binding.opens_menu() {
+                menu_self.display_context_menu(window_id);
             }
-            WindowAction::ResetWindowHeight => {
-                if let Err(e) = state.compositor().reset_window_height(window_id) {
-                    tracing::warn!(%e, id = window_id, "reset window height failed");
-                }
+            Self::execute_action(&state, &button, window_id, binding);
+        });
+
+        let resume = resume_drag.clone();
+        long_press.connect_end(move |gesture, _| resume(gesture.upcast_ref()));
+
+        let swipe = &self.gestures.swipe;
+        swipe.set_touch_only(true);
+        swipe.set_propagation_phase(gtk::PropagationPhase::Capture);
+
+        let state = self.state.clone();
+        let app_id = self.app_id.clone();
+        let title = self.title.clone();
+        let suspend = drag_suspended.clone();
+        swipe.connect_begin(move |gesture, _| {
+            let actions = state.settings().get_click_actions(app_id.as_deref(), title.borrow().as_deref());
+            let swipes_bound = [ClickBinding::SwipeUp, ClickBinding::SwipeDown, ClickBinding::SwipeLeft, ClickBinding::SwipeRight]
+                .into_iter()
+                .map(|binding| actions.resolve(binding, Modifiers::empty()))
+                .any(|binding| !binding.is_noop() || binding.opens_menu());
+
+            if let Some(widget) = gesture.widget().filter(|_| swipes_bound) {
+                widget.drag_source_unset();
+                suspend.set(true);
             }
-            WindowAction::SwitchPresetColumnWidth => {
-                if let Err(e) = state.compositor().switch_preset_column_width(window_id) {
-                    tracing::warn!(%e, id = window_id, "switch preset column width failed");
-                }
+        });
+
+        let state = self.state.clone();
+        let app_id = self.app_id.clone();
+        let title = self.title.clone();
+        let suppress_click = self.suppress_click.clone();
+        let menu_self = self.clone_for_menu();
+        swipe.connect_swipe(move |gesture, velocity_x, velocity_y| {
+            let Some(button) = gesture.widget().and_then(|w| w.downcast::<gtk::Button>().ok()) else {
+                return;
+            };
+            let Some(direction) = swipe_direction(velocity_x, velocity_y) else {
+                return;
+            };
+
+            let actions = state.settings().get_click_actions(app_id.as_deref(), title.borrow().as_deref());
+            let binding = actions.resolve(direction, Modifiers::empty());
+            if binding.is_noop() && !binding.opens_menu() {
+                return;
             }
-            WindowAction::SwitchPresetWindowHeight => {
-                if let Err(e) = state.compositor().switch_preset_window_height(window_id) {
-                    tracing::warn!(%e, id = window_id, "switch preset window height failed");
-                }
+
+            suppress_click.set(true);
+            if binding.opens_menu() {
+                menu_self.display_context_menu(window_id);
             }
-            WindowAction::MoveWindowToWorkspaceDown => {
-                if let Err(e) = state.compositor().move_window_to_workspace_down(window_id) {
-                    tracing::warn!(%e, id = window_id, "move window to workspace down failed");
+            Self::execute_action(&state, &button, window_id, binding);
+        });
+
+        swipe.connect_end(move |gesture, _| resume_drag(gesture.upcast_ref()));
+    }
+
+    fn setup_hover_handlers(&self, window_id: u64) {
+        self.gtk_button.add_events(gtk::gdk::EventMask::ENTER_NOTIFY_MASK | gtk::gdk::EventMask::LEAVE_NOTIFY_MASK);
+
+        let pending: Rc<RefCell<Option<gtk::glib::SourceId>>> = Rc::new(RefCell::new(None));
+
+        let schedule = {
+            let state = self.state.clone();
+            let app_id = self.app_id.clone();
+            let title = self.title.clone();
+            let pending = pending.clone();
+            move |button: &gtk::Button, binding: ClickBinding, modifiers: Modifiers| {
+                if pending.borrow().is_some() {
+                    return;
                 }
-            }
-            WindowAction::MoveWindowToWorkspaceUp => {
-                if let Err(e) = state.compositor().move_window_to_workspace_up(window_id) {
-                    tracing::warn!(%e, id = window_id, "move window to workspace up failed");
+
+                let actions = state.settings().get_click_actions(app_id.as_deref(), title.borrow().as_deref());
+                let sequence = actions.resolve(binding, modifiers).clone();
+                if sequence.is_noop() {
+                    return;
                 }
+
+                let state = state.clone();
+                let button = button.clone();
+                let fired = pending.clone();
+                let source = gtk::glib::timeout_add_local_once(actions.hover_delay(), move || {
+                    fired.borrow_mut().take();
+                    Self::execute_action(&state, &button, window_id, &sequence);
+                });
+                pending.replace(Some(source));
             }
-            WindowAction::MoveWindowToMonitorLeft => {
-                if let Err(e) = state.compositor().move_window_to_monitor_left(window_id) {
-                    tracing::warn!(%e, id = window_id, "move window to monitor left failed");
+        };
+
+        let cancel = {
+            let pending = pending.clone();
+            move || {
+                if let Some(source) = pending.borrow_mut().take() {
+                    source.remove();
                 }
             }
-            WindowAction::MoveWindowToMonitorRight => {
-                if let Err(e) = state.compositor().move_window_to_monitor_right(window_id) {
-                    tracing::warn!(%e, id = window_id, "move window to monitor right failed");
-                }
+        };
+
+        let schedule_hover = schedule.clone();
+        self.gtk_button.connect_enter_notify_event(move |button, event| {
+            if event.detail() != gtk::gdk::NotifyType::Inferior {
+                schedule_hover(button, ClickBinding::Hover, modifiers_from(event.state()));
             }
-            WindowAction::ToggleColumnTabbedDisplay => {
-                if let Err(e) = state.compositor().toggle_column_tabbed_display(window_id) {
-                    tracing::warn!(%e, id = window_id, "toggle column tabbed display failed");
-                }
+            gtk::glib::Propagation::Proceed
+        });
+
+        let cancel_hover = cancel.clone();
+        self.gtk_button.connect_leave_notify_event(move |_, event| {
+            if event.detail() != gtk::gdk::NotifyType::Inferior {
+                cancel_hover();
             }
-            WindowAction::FocusWorkspacePrevious => {
-                if let Err(e) = state.compositor().focus_workspace_previous(window_id) {
-                    tracing::warn!(%e, id = window_id, "focus workspace previous failed");
-                }
+            gtk::glib::Propagation::Proceed
+        });
+
+        let cancel_on_press = cancel.clone();
+        self.gtk_button.connect_button_press_event(move |_, _| {
+            cancel_on_press();
+            gtk::glib::Propagation::Proceed
+        });
+
+        self.gtk_button.connect_drag_motion(move |button, ctx, _, _, _| {
+            if ctx.drag_get_source_widget().is_none() {
+                schedule(button, ClickBinding::DragHover, Modifiers::empty());
             }
-            WindowAction::Menu => {}
+            false
+        });
+
+        self.gtk_button.connect_drag_leave(move |_, _, _| cancel());
+    }
+
+    fn execute_action(state: &SharedState, button: &gtk::Button, window_id: u64, actions: &ActionSequence) {
+        if actions.is_noop() {
+            return;
         }
+
+        Self::submit_action(state, button, window_id, ActionRequest::Perform(actions.clone()));
+    }
+
+    fn submit_action(state: &SharedState, button: &gtk::Button, window_id: u64, request: ActionRequest) {
+        let pending = state.actions().submit(window_id, request.clone());
+        let button = button.clone();
+
+        gtk::glib::spawn_future_local(async move {
+            if let Err(e) = pending.await {
+                tracing::warn!(%e, id = window_id, ?request, "window action failed");
+                Self::flag_action_failed(&button);
+            }
+        });
+    }
+
+    fn flag_action_failed(button: &gtk::Button) {
+        button.style_context().add_class("action-failed");
+
+        let button = button.clone();
+        gtk::glib::timeout_add_local_once(ACTION_FAILED_HIGHLIGHT, move || {
+            button.style_context().remove_class("action-failed");
+        });
     }
 
 	#[tracing::instrument(level = "TRACE", skip(self))]
 	fn display_context_menu(&self, window_id: u64) {
 		let menu = Menu::new();
-		menu.set_reserve_toggle_size(false);
-
 		let menu_items = self.state.settings().context_menu();
-		
+		let window = self.window.borrow().clone();
+		menu.set_reserve_toggle_size(
+		    menu_items.iter().any(|item| matches!(item.kind, MenuItemKind::Check(_) | MenuItemKind::Pin)),
+		);
+
 		for menu_item in menu_items {
-		    let item = MenuItem::with_label(&menu_item.label);
+		    let item = match menu_item.kind {
+		        MenuItemKind::Separator => {
+		            menu.append(&gtk::SeparatorMenuItem::new());
+		            continue;
+		        }
+		        MenuItemKind::Submenu(source) => {
+		            let item = MenuItem::with_label(&menu_item.label);
+		            item.set_submenu(Some(&match source {
+		                MenuSource::Workspaces => self.workspace_menu(window_id),
+		                MenuSource::Outputs => self.output_menu(window_id),
+		            }));
+		            menu.append(&item);
+		            continue;
+		        }
+		        MenuItemKind::Check(property) => {
+		            let item = gtk::CheckMenuItem::with_label(&menu_item.label);
+		            item.set_active(window.has_property(property));
+		            item.upcast::<MenuItem>()
+		        }
+		        MenuItemKind::Pin => {
+		            let Some(app_id) = self.app_id.clone() else {
+		                continue;
+		            };
+		            let item = gtk::CheckMenuItem::with_label(&menu_item.label);
+		            item.set_active(self.state.pinned().is_pinned(&app_id));
+
+		            let state = self.state.clone();
+		            let button = self.gtk_button.clone();
+		            item.connect_toggled(move |item| set_pinned(&state, &button, &app_id, item.is_active()));
+		            menu.append(&item);
+		            continue;
+		        }
+		        MenuItemKind::Action => MenuItem::with_label(&menu_item.label),
+		    };
+		    item.set_sensitive(menu_item.action.actions().iter().all(|action| window.supports(action)));
 		    menu.append(&item);
-		    
+
 		    let state = self.state.clone();
+		    let button = self.gtk_button.clone();
 		    let action = menu_item.action.clone();
 		    item.connect_activate(move |_| {
-		        Self::execute_action(&state, window_id, &action);
+		        Self::execute_action(&state, &button, window_id, &action);
 		    });
 		}
 
+		if self.state.settings().context_menu_desktop_actions() {
+		    self.append_desktop_actions(&menu);
+		}
+
 		menu.show_all();
 		menu.popup_at_pointer(None);
 	}
 
+	fn append_desktop_actions(&self, menu: &Menu) {
+		let Some(path) = self.app_id.as_deref().and_then(|id| self.state.icon_resolver().desktop_file(id)) else {
+		    return;
+		};
+		let Some(info) = gio::DesktopAppInfo::from_filename(&path) else {
+		    tracing::debug!(?path, "desktop entry could not be loaded");
+		    return;
+		};
+
+		if !menu.children().is_empty() {
+		    menu.append(&gtk::SeparatorMenuItem::new());
+		}
+		append_launch_items(menu, &self.gtk_button, &info);
+	}
+
+	fn workspace_menu(&self, window_id: u64) -> Menu {
+		let menu = Menu::new();
+		let current = self.window.borrow().workspace_id;
+		let groups = workspace_groups(self.state.compositor().current_workspaces());
+
+		if groups.is_empty() {
+		    menu.append(&placeholder_item("No workspaces"));
+		}
+
+		for (output, workspaces) in &groups {
+		    let target = if groups.len() > 1 {
+		        let submenu = Menu::new();
+		        let item = MenuItem::with_label(output.as_deref().unwrap_or("Unknown output"));
+		        item.set_submenu(Some(&submenu));
+		        menu.append(&item);
+		        submenu
+		    } else {
+		        menu.clone()
+		    };
+
+		    for workspace in workspaces {
+		        let item = MenuItem::with_label(&workspace_label(workspace));
+		        item.set_sensitive(Some(workspace.id) != current);
+
+		        let state = self.state.clone();
+		        let button = self.gtk_button.clone();
+		        let workspace_id = workspace.id;
+		        item.connect_activate(move |_| {
+		            Self::submit_action(&state, &button, window_id, ActionRequest::MoveToWorkspace { workspace_id });
+		        });
+		        target.append(&item);
+		    }
+		}
+
+		menu.show_all();
+		menu
+	}
+
+	fn output_menu(&self, window_id: u64) -> Menu {
+		let menu = Menu::new();
+		let loading = placeholder_item("Loading…");
+		menu.append(&loading);
+		menu.show_all();
+
+		let compositor = self.state.compositor().clone();
+		let current = self.window.borrow().get_output().map(str::to_string);
+		let state = self.state.clone();
+		let button = self.gtk_button.clone();
+		let target = menu.clone();
+
+		gtk::glib::spawn_future_local(async move {
+		    let outputs = match gio::spawn_blocking(move || compositor.query_outputs()).await {
+		        Ok(Ok(outputs)) => outputs,
+		        Ok(Err(e)) => {
+		            tracing::warn!(%e, "failed to query compositor outputs");
+		            loading.set_label("Outputs unavailable");
+		            return;
+		        }
+		        Err(_) => {
+		            tracing::error!("task spawning error");
+		            loading.set_label("Outputs unavailable");
+		            return;
+		        }
+		    };
+
+		    target.remove(&loading);
+		    for output in outputs.into_values().sorted_by(|a, b| a.name.cmp(&b.name)) {
+		        let item = MenuItem::with_label(&output_label(&output));
+		        item.set_sensitive(current.as_deref() != Some(output.name.as_str()));
+
+		        let state = state.clone();
+		        let button = button.clone();
+		        item.connect_activate(move |_| {
+		            let output = output.name.clone();
+		            Self::submit_action(&state, &button, window_id, ActionRequest::MoveToOutput { output });
+		        });
+		        target.append(&item);
+		    }
+		    target.show_all();
+		});
+
+		menu
+	}
+
 	fn clone_for_menu(&self) -> Self {
 		Self {
 		    app_id: self.app_id.clone(),
@@ -363,41 +698,30 @@ impl WindowButton {
 		    display_titles: self.display_titles,
 		    state: self.state.clone(),
 		    window_id: self.window_id,
+		    window: self.window.clone(),
 		    title: self.title.clone(),
+		    is_focused: self.is_focused.clone(),
+		    suppress_click: self.suppress_click.clone(),
+		    gestures: self.gestures.clone(),
 		}
 	}
 
     fn setup_drag_reorder(&self) {
         tracing::info!("configuring drag-drop for window {}", self.window_id);
 
-        let drag_targets = vec![TargetEntry::new("text/plain", TargetFlags::SAME_APP, 0)];
-
-        self.gtk_button.drag_source_set(
-            gtk::gdk::ModifierType::BUTTON1_MASK,
-            &drag_targets,
-            gtk::gdk::DragAction::MOVE,
-        );
+        enable_drag_source(self.gtk_button.upcast_ref());
 
         self.gtk_button.drag_dest_set(
             DestDefaults::ALL,
-            &drag_targets,
+            &drag_targets(),
             gtk::gdk::DragAction::MOVE,
         );
+        self.gtk_button.drag_dest_set_track_motion(true);
 
-        let initial_position = Rc::new(RefCell::new(0));
-        let pos_for_begin = initial_position.clone();
-
+        let dragged = self.window.clone();
         self.gtk_button.connect_drag_begin(move |widget, _| {
             tracing::info!("drag initiated");
-
-            if let Some(parent) = widget.parent() {
-                if let Ok(container) = parent.downcast::<gtk::Box>() {
-                    let position = container.child_position(widget);
-                    *pos_for_begin.borrow_mut() = position;
-                    tracing::info!("stored initial position: {}", position);
-                }
-            }
-
+            DRAGGED.with(|window| *window.borrow_mut() = Some(dragged.borrow().clone()));
             widget.style_context().add_class("dragging");
         });
 
@@ -409,66 +733,56 @@ impl WindowButton {
         let button_for_end = self.gtk_button.clone();
         self.gtk_button.connect_drag_end(move |_, _| {
             tracing::info!("drag completed");
+            DRAGGED.with(|window| window.borrow_mut().take());
             button_for_end.style_context().remove_class("dragging");
         });
 
-        self.gtk_button.connect_drag_motion(move |widget, ctx, _x, _y, _time| {
-            if let Some(source) = ctx.drag_get_source_widget() {
-                if source != *widget {
-                    if let Some(parent) = widget.parent() {
-                        if let Ok(container) = parent.downcast::<gtk::Box>() {
-                            let source_pos = container.child_position(&source);
-                            let target_pos = container.child_position(widget);
-
-                            if source_pos != target_pos {
-                                container.reorder_child(&source, target_pos);
-                                tracing::trace!("reordered from {} to {}", source_pos, target_pos);
-                            }
-                        }
-                    }
-                }
+        let target = self.window.clone();
+        self.gtk_button.connect_drag_motion(move |widget, ctx, x, _y, time| {
+            let dragging_other = DRAGGED.with(|dragged| dragged.borrow().as_ref().is_some_and(|w| w.id != target.borrow().id));
+            if !dragging_other || target.borrow().is_floating {
+                show_drop_zone(widget, None);
+                ctx.drag_status(gtk::gdk::DragAction::empty(), time);
+                return true;
             }
+
+            show_drop_zone(widget, Some(DropZone::at(x, widget.allocated_width())));
+            ctx.drag_status(gtk::gdk::DragAction::MOVE, time);
             true
         });
 
-        let button_for_leave = self.gtk_button.clone();
-        self.gtk_button.connect_drag_leave(move |_, _, _| {
-            button_for_leave.style_context().remove_class("drag-over");
-        });
+        self.gtk_button.connect_drag_leave(|widget, _, _| show_drop_zone(widget, None));
 
         let state = self.state.clone();
-        let pos_for_drop = initial_position.clone();
-        self.gtk_button.connect_drag_data_received(move |_widget, ctx, _, _, data, _, time| {
+        let target = self.window.clone();
+        self.gtk_button.connect_drag_data_received(move |widget, ctx, x, _, _, _, time| {
             tracing::info!("drop received");
-
-            if let Some(text) = data.text() {
-                if let Ok(dragged_window_id) = text.parse::<u64>() {
-                    if let Some(source) = ctx.drag_get_source_widget() {
-                        if let Some(parent) = source.parent() {
-                            if let Ok(container) = parent.downcast::<gtk::Box>() {
-                                let start_pos = *pos_for_drop.borrow();
-                                let end_pos = container.child_position(&source);
-                                let delta = end_pos - start_pos;
-
-                                tracing::info!("position change: {} -> {} (delta: {})", start_pos, end_pos, delta);
-
-                                match state.compositor().reposition_window(dragged_window_id, delta) {
-                                    Ok(()) => {
-                                        tracing::info!("reposition successful");
-                                        ctx.drag_finish(true, false, time);
-                                        return;
-                                    }
-                                    Err(e) => {
-                                        tracing::error!("reposition failed: {}", e);
-                                    }
-                                }
-                            }
-                        }
-                    }
+            show_drop_zone(widget, None);
+
+            let Some(dragged) = DRAGGED.with(|dragged| dragged.borrow().clone()) else {
+                tracing::warn!("drop without a known dragged window");
+                Self::flag_action_failed(widget);
+                ctx.drag_finish(false, false, time);
+                return;
+            };
+
+            let target = target.borrow().clone();
+            let zone = DropZone::at(x, widget.allocated_width());
+            let same_workspace = dragged.workspace().id == target.workspace().id;
+
+            // In manual order, dropping beside a button only rearranges the bar.
+            if same_workspace && zone != DropZone::Into && state.settings().sort_by() == SortOrder::Manual {
+                if let Some(source) = ctx.drag_get_source_widget() {
+                    place_beside(&source, widget.upcast_ref(), zone == DropZone::After);
                 }
+                state.bar_order().dropped();
+                ctx.drag_finish(true, false, time);
+                return;
             }
 
-            ctx.drag_finish(false, false, time);
+            let request = ActionRequest::Drop { target_id: target.id, zone };
+            Self::submit_action(&state, widget, dragged.id, request);
+            ctx.drag_finish(true, false, time);
         });
     }
 
@@ -498,21 +812,7 @@ impl WindowButton {
             if needs_render {
                 let dimension = icon_dimension;
 
-                let icon_image = Self::load_icon_image(icon_path.as_ref(), button, dimension)
-                    .unwrap_or_else(|| {
-                        static FALLBACK: &str = "application-x-executable";
-
-                        ICON_THEME_INSTANCE.with(|theme| {
-                            theme.lookup_icon_for_scale(
-                                FALLBACK,
-                                dimension,
-                                button.scale_factor(),
-                                IconLookupFlags::empty(),
-                            )
-                        })
-                        .and_then(|info| Self::load_icon_image(info.filename().as_ref(), button, dimension))
-                        .unwrap_or_else(|| gtk::Image::from_icon_name(Some(FALLBACK), IconSize::Button))
-                    });
+                let icon_image = Self::icon_image(icon_path.as_ref(), button, dimension);
 
                 let container_copy = container.clone();
                 let label_copy = label.clone();
@@ -535,6 +835,23 @@ impl WindowButton {
         });
     }
 
+    fn icon_image(path: Option<&PathBuf>, button: &gtk::Button, size: i32) -> gtk::Image {
+        Self::load_icon_image(path, button, size).unwrap_or_else(|| {
+            static FALLBACK: &str = "application-x-executable";
+
+            ICON_THEME_INSTANCE.with(|theme| {
+                theme.lookup_icon_for_scale(
+                    FALLBACK,
+                    size,
+                    button.scale_factor(),
+                    IconLookupFlags::empty(),
+                )
+            })
+            .and_then(|info| Self::load_icon_image(info.filename().as_ref(), button, size))
+            .unwrap_or_else(|| gtk::Image::from_icon_name(Some(FALLBACK), IconSize::Button))
+        })
+    }
+
     fn load_icon_image(
         path: Option<&PathBuf>,
         button: &gtk::Button,
@@ -564,4 +881,223 @@ impl WindowButton {
 		    }
 		}
 	}
-}
\ No newline at end of file
+}
+
+fn append_launch_items(menu: &Menu, button: &gtk::Button, info: &gio::DesktopAppInfo) {
+    let launch_actions = std::iter::once((None, "Launch New Instance".to_string()))
+        .chain(info.list_actions().into_iter().map(|action| {
+            let label = info.action_name(&action).to_string();
+            (Some(action.to_string()), label)
+        }));
+
+    for (action, label) in launch_actions {
+        let item = MenuItem::with_label(&label);
+        let info = info.clone();
+        let button = button.clone();
+        item.connect_activate(move |_| launch_app(&button, &info, action.as_deref()));
+        menu.append(&item);
+    }
+}
+
+fn launch_app(button: &gtk::Button, info: &gio::DesktopAppInfo, action: Option<&str>) {
+    use gio::prelude::AppInfoExt;
+
+    let context = button.display().app_launch_context();
+    if let Some(context) = &context {
+        context.set_timestamp(gtk::current_event_time());
+    }
+
+    let launched = match action {
+        Some(action) => {
+            info.launch_action(action, context.as_ref());
+            Ok(())
+        }
+        None => info.launch(&[], context.as_ref()),
+    };
+    if let Err(e) = launched {
+        tracing::warn!(%e, path = ?info.filename(), "failed to launch application");
+        WindowButton::flag_action_failed(button);
+    }
+}
+
+fn set_pinned(state: &SharedState, button: &gtk::Button, app_id: &str, pinned: bool) {
+    let result = if pinned {
+        state.pinned().pin(app_id)
+    } else {
+        state.pinned().unpin(app_id)
+    };
+    if let Err(e) = result {
+        tracing::warn!(%e, app_id, pinned, "failed to save pinned apps");
+        WindowButton::flag_action_failed(button);
+    }
+}
+
+fn placeholder_item(label: &str) -> MenuItem {
+    let item = MenuItem::with_label(label);
+    item.set_sensitive(false);
+    item
+}
+
+fn workspace_groups(workspaces: Vec<Workspace>) -> Vec<(Option<String>, Vec<Workspace>)> {
+    workspaces
+        .into_iter()
+        .sorted_by(|a, b| (&a.output, a.idx).cmp(&(&b.output, b.idx)))
+        .chunk_by(|ws| ws.output.clone())
+        .into_iter()
+        .map(|(output, group)| (output, group.collect()))
+        .collect()
+}
+
+fn workspace_label(workspace: &Workspace) -> String {
+    workspace.name.clone().unwrap_or_else(|| workspace.idx.to_string())
+}
+
+fn output_label(output: &Output) -> String {
+    format!("{} ({} {})", output.name, output.make, output.model)
+}
+
+fn show_drop_zone(widget: &gtk::Button, zone: Option<DropZone>) {
+    let style_ctx = widget.style_context();
+    for (class, shown) in [
+        ("drop-before", zone == Some(DropZone::Before)),
+        ("drop-into", zone == Some(DropZone::Into)),
+        ("drop-after", zone == Some(DropZone::After)),
+    ] {
+        if shown {
+            style_ctx.add_class(class);
+        } else {
+            style_ctx.remove_class(class);
+        }
+    }
+}
+
+/// Moves `source` next to `target`, into the target's container if it's in another one.
+fn place_beside(source: &gtk::Widget, target: &gtk::Widget, after: bool) {
+    let Some(parent) = target.parent().and_then(|parent| parent.downcast::<gtk::Box>().ok()) else {
+        return;
+    };
+
+    if source.parent().as_ref() != Some(parent.upcast_ref()) {
+        if let Some(previous) = source.parent().and_then(|previous| previous.downcast::<gtk::Container>().ok()) {
+            previous.remove(source);
+        }
+        parent.add(source);
+    }
+
+    let source_pos = parent.child_position(source);
+    let target_pos = parent.child_position(target);
+    let position = target_pos + i32::from(after) - i32::from(source_pos < target_pos);
+    parent.reorder_child(source, position);
+}
+
+fn drag_targets() -> Vec<TargetEntry> {
+    vec![TargetEntry::new("text/plain", TargetFlags::SAME_APP, 0)]
+}
+
+fn enable_drag_source(widget: &gtk::Widget) {
+    widget.drag_source_set(
+        gtk::gdk::ModifierType::BUTTON1_MASK,
+        &drag_targets(),
+        gtk::gdk::DragAction::MOVE,
+    );
+}
+
+fn swipe_direction(velocity_x: f64, velocity_y: f64) -> Option<ClickBinding> {
+    const MIN_SWIPE_VELOCITY: f64 = 300.0;
+
+    if velocity_x.abs().max(velocity_y.abs()) < MIN_SWIPE_VELOCITY {
+        None
+    } else if velocity_y.abs() >= velocity_x.abs() {
+        Some(if velocity_y < 0.0 { ClickBinding::SwipeUp } else { ClickBinding::SwipeDown })
+    } else {
+        Some(if velocity_x < 0.0 { ClickBinding::SwipeLeft } else { ClickBinding::SwipeRight })
+    }
+}
+
+fn scroll_binding(
+    actions: &ClickActions,
+    direction: gtk::gdk::ScrollDirection,
+    modifiers: Modifiers,
+) -> Option<&ActionSequence> {
+    use gtk::gdk::ScrollDirection;
+
+    let binding = match direction {
+        ScrollDirection::Up => ClickBinding::ScrollUp,
+        ScrollDirection::Down => ClickBinding::ScrollDown,
+        ScrollDirection::Left => ClickBinding::ScrollLeft,
+        ScrollDirection::Right => ClickBinding::ScrollRight,
+        _ => return None,
+    };
+    Some(actions.resolve(binding, modifiers))
+}
+
+fn modifiers_from(state: gtk::gdk::ModifierType) -> Modifiers {
+    use gtk::gdk::ModifierType;
+
+    [
+        (ModifierType::SHIFT_MASK, Modifiers::SHIFT),
+        (ModifierType::CONTROL_MASK, Modifiers::CTRL),
+        (ModifierType::MOD1_MASK, Modifiers::ALT),
+        (ModifierType::SUPER_MASK | ModifierType::MOD4_MASK, Modifiers::SUPER),
+    ]
+    .into_iter()
+    .filter(|(mask, _)| state.intersects(*mask))
+    .fold(Modifiers::empty(), |modifiers, (_, modifier)| modifiers | modifier)
+}
+
+#[derive(Debug, Default)]
+struct ScrollAccumulator {
+    dx: f64,
+    dy: f64,
+}
+
+impl ScrollAccumulator {
+    const STEP: f64 = 1.0;
+
+    fn push(&mut self, dx: f64, dy: f64) -> Option<gtk::gdk::ScrollDirection> {
+        use gtk::gdk::ScrollDirection;
+
+        if self.dx * dx < 0.0 {
+            self.dx = 0.0;
+        }
+        if self.dy * dy < 0.0 {
+            self.dy = 0.0;
+        }
+        self.dx += dx;
+        self.dy += dy;
+
+        if self.dy.abs() >= Self::STEP && self.dy.abs() >= self.dx.abs() {
+            let direction = if self.dy > 0.0 { ScrollDirection::Down } else { ScrollDirection::Up };
+            self.dy = 0.0;
+            Some(direction)
+        } else if self.dx.abs() >= Self::STEP {
+            let direction = if self.dx > 0.0 { ScrollDirection::Right } else { ScrollDirection::Left };
+            self.dx = 0.0;
+            Some(direction)
+        } else {
+            None
+        }
+    }
+}
+
+/// Remembers the order a group was first cycled in, so repeated clicks visit every window
+/// instead of bouncing between the two most recent ones.
+#[derive(Debug, Default)]
+struct GroupCycle {
+    order: Vec<u64>,
+}
+
+impl GroupCycle {
+    fn next(&mut self, mru: &[u64], focused: Option<u64>) -> Option<u64> {
+        let same_members = self.order.len() == mru.len() && mru.iter().all(|id| self.order.contains(id));
+        if focused.is_none() || !same_members {
+            self.order = mru.to_vec();
+        }
+
+        let Some(focused) = focused else {
+            return self.order.first().copied();
+        };
+        let position = self.order.iter().position(|id| *id == focused)?;
+        self.order.get((position + 1) % self.order.len()).copied()
+    }
+}
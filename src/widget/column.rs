@@ -0,0 +1,34 @@
+use waybar_cffi::gtk::{
+    self as gtk, Orientation,
+    prelude::{StyleContextExt, WidgetExt},
+};
+use super::BUTTON_STYLES;
+
+/// Holds the buttons of a niri column with more than one tile.
+pub struct ColumnBox {
+    container: gtk::Box,
+}
+
+impl ColumnBox {
+    pub fn create() -> Self {
+        let container = gtk::Box::new(Orientation::Horizontal, 0);
+        container.style_context().add_class("column");
+
+        BUTTON_STYLES.with(|provider| {
+            container.style_context().add_provider(provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+        });
+
+        Self { container }
+    }
+
+    pub fn update(&self, tabbed: bool) {
+        let style_ctx = self.container.style_context();
+        let (add, remove) = if tabbed { ("column-tabbed", "column-stacked") } else { ("column-stacked", "column-tabbed") };
+        style_ctx.add_class(add);
+        style_ctx.remove_class(remove);
+    }
+
+    pub fn get_widget(&self) -> &gtk::Box {
+        &self.container
+    }
+}
@@ -0,0 +1,181 @@
+use std::{cell::RefCell, rc::Rc};
+use waybar_cffi::gtk::{
+    self as gtk, Orientation, ReliefStyle,
+    glib::clone::Downgrade,
+    prelude::{BinExt, BoxExt, ButtonExt, Cast, ContainerExt, LabelExt, PopoverExt, StyleContextExt, WidgetExt},
+};
+use crate::{
+    actions::ActionRequest,
+    compositor::WindowInfo,
+    global::SharedState,
+    settings::{GroupClick, WindowAction},
+};
+use super::{BUTTON_STYLES, GroupCycle, WindowButton};
+
+/// One button standing in for every window of an app.
+pub struct GroupButton {
+    gtk_button: gtk::Button,
+    title_label: gtk::Label,
+    badge: gtk::Label,
+    reserved_width: i32,
+    members: Rc<RefCell<Vec<WindowInfo>>>,
+}
+
+impl GroupButton {
+    #[tracing::instrument(level = "TRACE", skip(state))]
+    pub fn create(state: &SharedState, app_id: &str) -> Self {
+        let display_titles = state.settings().show_window_titles();
+        let layout_box = gtk::Box::new(Orientation::Horizontal, state.settings().icon_spacing());
+
+        let title_label = gtk::Label::new(None);
+        title_label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        title_label.set_xalign(0.0);
+
+        let badge = gtk::Label::new(None);
+        badge.style_context().add_class("count");
+
+        let gtk_button = gtk::Button::new();
+        gtk_button.set_relief(ReliefStyle::None);
+        gtk_button.style_context().add_class("group");
+        gtk_button.add(&layout_box);
+
+        BUTTON_STYLES.with(|provider| {
+            gtk_button.style_context().add_provider(provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+            badge.style_context().add_provider(provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+        });
+
+        if display_titles {
+            layout_box.pack_start(&title_label, true, true, 0);
+        }
+        layout_box.pack_end(&badge, false, false, 0);
+
+        let icon_path = state.icon_resolver().resolve(app_id);
+        let icon_size = state.settings().icon_size();
+        let reserved_width = icon_size + 2 * state.settings().icon_spacing() + 32;
+        let icon_box = layout_box.clone();
+        gtk_button.connect_realize(move |button| {
+            let image = WindowButton::icon_image(icon_path.as_ref(), button, icon_size);
+            icon_box.pack_start(&image, false, false, 0);
+            icon_box.reorder_child(&image, 0);
+            image.show();
+        });
+
+        let members: Rc<RefCell<Vec<WindowInfo>>> = Rc::default();
+        let popover = gtk::Popover::new(Some(&gtk_button));
+
+        let click_state = state.clone();
+        let click_members = members.clone();
+        let click_popover = popover.clone();
+        let cycle = RefCell::new(GroupCycle::default());
+        gtk_button.connect_clicked(move |button| match click_state.settings().group_click() {
+            GroupClick::Cycle => {
+                let members = click_members.borrow();
+                let order: Vec<u64> = members.iter().map(|w| w.id).collect();
+                let focused = members.iter().find(|w| w.is_focused).map(|w| w.id);
+
+                if let Some(window_id) = cycle.borrow_mut().next(&order, focused) {
+                    let request = ActionRequest::Perform(WindowAction::FocusWindow.into());
+                    WindowButton::submit_action(&click_state, button, window_id, request);
+                }
+            }
+            GroupClick::List => show_window_list(&click_state, button, &click_popover, &click_members.borrow()),
+        });
+
+        let state = state.clone();
+        let list_members = members.clone();
+        gtk_button.connect_button_press_event(move |button, event| {
+            if event.button() != 3 {
+                return gtk::glib::Propagation::Proceed;
+            }
+            show_window_list(&state, button, &popover, &list_members.borrow());
+            gtk::glib::Propagation::Stop
+        });
+
+        Self { gtk_button, title_label, badge, reserved_width, members }
+    }
+
+    /// Takes the group's current windows, most recently focused first.
+    pub fn update(&self, mut members: Vec<WindowInfo>) {
+        members.sort_by(|a, b| {
+            let recency = |w: &WindowInfo| w.focus_timestamp.map(|t| (t.secs, t.nanos));
+            recency(b).cmp(&recency(a)).then(a.id.cmp(&b.id))
+        });
+
+        let style_ctx = self.gtk_button.style_context();
+        let focused = members.iter().any(|w| w.is_focused);
+        let urgent = members.iter().any(|w| w.is_urgent && !w.is_focused);
+        for (class, enabled) in [("focused", focused), ("urgent", urgent)] {
+            if enabled {
+                style_ctx.add_class(class);
+            } else {
+                style_ctx.remove_class(class);
+            }
+        }
+
+        let current = members.iter().find(|w| w.is_focused).or(members.first());
+        self.title_label.set_text(current.and_then(|w| w.title.as_deref()).unwrap_or_default());
+        self.badge.set_text(&members.len().to_string());
+
+        let titles: Vec<&str> = members.iter().filter_map(|w| w.title.as_deref()).collect();
+        self.gtk_button.set_tooltip_text(Some(&titles.join("\n")));
+
+        *self.members.borrow_mut() = members;
+    }
+
+    pub fn contains(&self, window_id: u64) -> bool {
+        self.members.borrow().iter().any(|w| w.id == window_id)
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.members.borrow().iter().any(|w| w.is_focused)
+    }
+
+    pub fn mark_urgent(&self) {
+        self.gtk_button.style_context().add_class("urgent");
+    }
+
+    pub fn get_widget(&self) -> &gtk::Button {
+        &self.gtk_button
+    }
+
+    pub fn resize_for_width(&self, width: i32) {
+        self.title_label.set_max_width_chars(((width - self.reserved_width) / 8).max(0));
+    }
+}
+
+fn show_window_list(state: &SharedState, button: &gtk::Button, popover: &gtk::Popover, members: &[WindowInfo]) {
+    if let Some(previous) = popover.child() {
+        popover.remove(&previous);
+    }
+
+    let list = gtk::Box::new(Orientation::Vertical, 0);
+    for window in members {
+        let entry = gtk::Button::with_label(window.title.as_deref().unwrap_or_default());
+        entry.set_relief(ReliefStyle::None);
+        if let Some(label) = entry.child().and_then(|child| child.downcast::<gtk::Label>().ok()) {
+            label.set_xalign(0.0);
+        }
+        if window.is_focused {
+            entry.style_context().add_class("focused");
+        }
+
+        let state = state.clone();
+        let button = button.downgrade();
+        let popover = popover.downgrade();
+        let window_id = window.id;
+        entry.connect_clicked(move |_| {
+            if let Some(popover) = popover.upgrade() {
+                popover.popdown();
+            }
+            if let Some(button) = button.upgrade() {
+                let request = ActionRequest::Perform(WindowAction::FocusWindow.into());
+                WindowButton::submit_action(&state, &button, window_id, request);
+            }
+        });
+        list.pack_start(&entry, false, false, 0);
+    }
+
+    popover.add(&list);
+    list.show_all();
+    popover.popup();
+}
@@ -0,0 +1,79 @@
+use waybar_cffi::gtk::{
+    self as gtk, Menu, MenuItem, ReliefStyle, gio,
+    prelude::{AppInfoExt, ButtonExt, GtkMenuExt, GtkMenuItemExt, MenuShellExt, StyleContextExt, WidgetExt},
+};
+use crate::global::SharedState;
+use super::{BUTTON_STYLES, WindowButton, append_launch_items, launch_app, set_pinned};
+
+/// Stand-in for a pinned app that has no windows on this bar.
+pub struct LauncherButton {
+    gtk_button: gtk::Button,
+}
+
+impl LauncherButton {
+    #[tracing::instrument(level = "TRACE", skip(state))]
+    pub fn create(state: &SharedState, pin: &str) -> Self {
+        let app_id = pin.strip_suffix(".desktop").unwrap_or(pin).to_string();
+        let info = state
+            .icon_resolver()
+            .desktop_file(&app_id)
+            .and_then(|path| gio::DesktopAppInfo::from_filename(&path));
+
+        let gtk_button = gtk::Button::new();
+        gtk_button.set_always_show_image(true);
+        gtk_button.set_relief(ReliefStyle::None);
+        gtk_button.style_context().add_class("launcher");
+        let name = info.as_ref().map_or(app_id.clone(), |info| info.display_name().to_string());
+        gtk_button.set_tooltip_text(Some(&name));
+
+        BUTTON_STYLES.with(|provider| {
+            gtk_button.style_context().add_provider(provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+        });
+
+        let icon_path = state.icon_resolver().resolve(&app_id);
+        let icon_size = state.settings().icon_size();
+        gtk_button.connect_realize(move |button| {
+            button.set_image(Some(&WindowButton::icon_image(icon_path.as_ref(), button, icon_size)));
+        });
+
+        let launch_info = info.clone();
+        let launch_app_id = app_id.clone();
+        gtk_button.connect_clicked(move |button| match &launch_info {
+            Some(info) => launch_app(button, info, None),
+            None => {
+                tracing::warn!(app_id = launch_app_id, "no desktop entry to launch pinned app from");
+                WindowButton::flag_action_failed(button);
+            }
+        });
+
+        let state = state.clone();
+        gtk_button.connect_button_press_event(move |button, event| {
+            if event.button() != 3 {
+                return gtk::glib::Propagation::Proceed;
+            }
+
+            let menu = Menu::new();
+            if let Some(info) = &info {
+                append_launch_items(&menu, button, info);
+                menu.append(&gtk::SeparatorMenuItem::new());
+            }
+
+            let unpin = MenuItem::with_label("Unpin from Bar");
+            let state = state.clone();
+            let app_id = app_id.clone();
+            let target = button.clone();
+            unpin.connect_activate(move |_| set_pinned(&state, &target, &app_id, false));
+            menu.append(&unpin);
+
+            menu.show_all();
+            menu.popup_at_pointer(None);
+            gtk::glib::Propagation::Stop
+        });
+
+        Self { gtk_button }
+    }
+
+    pub fn get_widget(&self) -> &gtk::Button {
+        &self.gtk_button
+    }
+}
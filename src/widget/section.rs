@@ -0,0 +1,61 @@
+use waybar_cffi::gtk::{
+    self as gtk, ReliefStyle,
+    prelude::{ButtonExt, StyleContextExt, WidgetExt},
+};
+use crate::{compositor::WorkspaceInfo, global::SharedState};
+use super::{BUTTON_STYLES, WindowButton};
+
+/// Label leading a workspace's windows; clicking it focuses the workspace.
+pub struct SectionLabel {
+    gtk_button: gtk::Button,
+}
+
+impl SectionLabel {
+    #[tracing::instrument(level = "TRACE", skip(state))]
+    pub fn create(state: &SharedState, workspace_id: u64) -> Self {
+        let gtk_button = gtk::Button::new();
+        gtk_button.set_relief(ReliefStyle::None);
+        gtk_button.set_can_focus(false);
+        gtk_button.style_context().add_class("workspace-section");
+
+        BUTTON_STYLES.with(|provider| {
+            gtk_button.style_context().add_provider(provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+        });
+
+        let state = state.clone();
+        gtk_button.connect_clicked(move |button| {
+            let pending = state.actions().focus_workspace(workspace_id);
+            let button = button.clone();
+            gtk::glib::spawn_future_local(async move {
+                if let Err(e) = pending.await {
+                    tracing::warn!(%e, workspace_id, "failed to focus workspace");
+                    WindowButton::flag_action_failed(&button);
+                }
+            });
+        });
+
+        Self { gtk_button }
+    }
+
+    pub fn update(&self, workspace: &WorkspaceInfo, output: Option<&str>) {
+        let name = workspace.name.clone().unwrap_or_else(|| workspace.idx.to_string());
+        let label = match output {
+            Some(output) => format!("{output}: {name}"),
+            None => name,
+        };
+        self.gtk_button.set_label(&label);
+
+        let style_ctx = self.gtk_button.style_context();
+        for (class, enabled) in [("active", workspace.is_active), ("focused", workspace.is_focused)] {
+            if enabled {
+                style_ctx.add_class(class);
+            } else {
+                style_ctx.remove_class(class);
+            }
+        }
+    }
+
+    pub fn get_widget(&self) -> &gtk::Button {
+        &self.gtk_button
+    }
+}
@@ -0,0 +1,81 @@
+use waybar_cffi::gtk::gdk::{ModifierType, ScrollDirection};
+use super::*;
+use crate::testing::workspace;
+
+#[test]
+fn smooth_scroll_fires_once_per_step() {
+    let mut accumulator = ScrollAccumulator::default();
+
+    let fired: Vec<_> = std::iter::repeat_n(0.25, 9).filter_map(|dy| accumulator.push(0.0, dy)).collect();
+
+    assert_eq!(fired, [ScrollDirection::Down, ScrollDirection::Down]);
+}
+
+#[test]
+fn large_smooth_delta_fires_a_single_action() {
+    let mut accumulator = ScrollAccumulator::default();
+
+    assert_eq!(accumulator.push(0.0, -12.0), Some(ScrollDirection::Up));
+    assert_eq!(accumulator.push(0.0, -0.5), None);
+}
+
+#[test]
+fn reversing_direction_discards_partial_scroll() {
+    let mut accumulator = ScrollAccumulator::default();
+
+    assert_eq!(accumulator.push(0.9, 0.0), None);
+    assert_eq!(accumulator.push(-0.9, 0.0), None);
+    assert_eq!(accumulator.push(-0.2, 0.0), Some(ScrollDirection::Left));
+}
+
+#[test]
+fn gdk_modifiers_map_to_binding_modifiers() {
+    let state = ModifierType::SHIFT_MASK | ModifierType::MOD4_MASK | ModifierType::BUTTON1_MASK | ModifierType::LOCK_MASK;
+
+    assert_eq!(modifiers_from(state), Modifiers::SHIFT | Modifiers::SUPER);
+    assert_eq!(modifiers_from(ModifierType::MOD2_MASK), Modifiers::empty());
+}
+
+#[test]
+fn swipe_direction_follows_dominant_axis() {
+    assert_eq!(swipe_direction(50.0, -900.0), Some(ClickBinding::SwipeUp));
+    assert_eq!(swipe_direction(-20.0, 400.0), Some(ClickBinding::SwipeDown));
+    assert_eq!(swipe_direction(-700.0, 300.0), Some(ClickBinding::SwipeLeft));
+    assert_eq!(swipe_direction(120.0, 80.0), None);
+}
+
+#[test]
+fn workspace_submenu_groups_by_output_in_index_order() {
+    let mut named = workspace(4, 1, "HDMI-A-1", false);
+    named.name = Some("chat".to_string());
+    let groups = workspace_groups(vec![
+        workspace(3, 2, "DP-1", false),
+        named,
+        workspace(1, 1, "DP-1", true),
+    ]);
+
+    let labels: Vec<_> = groups
+        .iter()
+        .map(|(output, workspaces)| (output.as_deref(), workspaces.iter().map(workspace_label).collect::<Vec<_>>()))
+        .collect();
+    assert_eq!(
+        labels,
+        [
+            (Some("DP-1"), vec!["1".to_string(), "2".to_string()]),
+            (Some("HDMI-A-1"), vec!["chat".to_string()]),
+        ]
+    );
+}
+
+#[test]
+fn group_cycle_visits_every_window_once() {
+    let mut cycle = GroupCycle::default();
+
+    assert_eq!(cycle.next(&[3, 1, 2], None), Some(3));
+    assert_eq!(cycle.next(&[3, 1, 2], Some(3)), Some(1));
+    assert_eq!(cycle.next(&[1, 3, 2], Some(1)), Some(2));
+    assert_eq!(cycle.next(&[2, 1, 3], Some(2)), Some(3));
+
+    assert_eq!(cycle.next(&[4, 2, 1, 3], Some(4)), Some(2), "membership change restarts from MRU order");
+    assert_eq!(cycle.next(&[], None), None);
+}